@@ -1,12 +1,42 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261018_000001_create_wiktionary_table;
+mod m20261018_000002_create_wordnet_tables;
+mod m20261018_000003_create_cedict_table;
+mod m20261018_000004_create_words_fts;
+mod m20261018_000005_create_word_forms_table;
+mod m20261018_000006_add_words_lookup_indexes;
+mod m20261018_000007_create_ecdict_imports_table;
+mod m20261018_000008_create_ecdict_companion_tables;
+mod m20261018_000009_create_cache_table;
 
+/// Migrations of the offline dictionaries database.
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261018_000001_create_wiktionary_table::Migration),
+            Box::new(m20261018_000002_create_wordnet_tables::Migration),
+            Box::new(m20261018_000003_create_cedict_table::Migration),
+            Box::new(m20261018_000004_create_words_fts::Migration),
+            Box::new(m20261018_000005_create_word_forms_table::Migration),
+            Box::new(m20261018_000006_add_words_lookup_indexes::Migration),
+            Box::new(m20261018_000007_create_ecdict_imports_table::Migration),
+            Box::new(m20261018_000008_create_ecdict_companion_tables::Migration),
+        ]
+    }
+}
+
+/// Migrations of the query cache database, kept apart from the dictionaries.
+pub struct CacheMigrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for CacheMigrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![Box::new(m20261018_000009_create_cache_table::Migration)]
     }
 }
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Wiktionary::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Wiktionary::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Wiktionary::Word).text().not_null())
+                    .col(ColumnDef::new(Wiktionary::Lang).text().not_null())
+                    .col(ColumnDef::new(Wiktionary::LangCode).text().not_null())
+                    .col(ColumnDef::new(Wiktionary::Pos).text())
+                    .col(ColumnDef::new(Wiktionary::Etymology).text())
+                    // json arrays, see `bin/wiktionary.rs`
+                    .col(ColumnDef::new(Wiktionary::Senses).text())
+                    .col(ColumnDef::new(Wiktionary::Forms).text())
+                    .col(ColumnDef::new(Wiktionary::Sounds).text())
+                    .col(ColumnDef::new(Wiktionary::Translations).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wiktionary_word_lang_code")
+                    .table(Wiktionary::Table)
+                    .col(Wiktionary::Word)
+                    .col(Wiktionary::LangCode)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Wiktionary::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Wiktionary {
+    Table,
+    Id,
+    Word,
+    Lang,
+    LangCode,
+    Pos,
+    Etymology,
+    Senses,
+    Forms,
+    Sounds,
+    Translations,
+}
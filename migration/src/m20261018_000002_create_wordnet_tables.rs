@@ -0,0 +1,156 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordnetSynsets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WordnetSynsets::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    // `{offset}-{pos}`, e.g. `02084071-n`
+                    .col(
+                        ColumnDef::new(WordnetSynsets::Synset)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(WordnetSynsets::Pos).text().not_null())
+                    .col(ColumnDef::new(WordnetSynsets::Gloss).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordnetSenses::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WordnetSenses::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    // lower case, words separated by space
+                    .col(ColumnDef::new(WordnetSenses::Lemma).text().not_null())
+                    .col(ColumnDef::new(WordnetSenses::Word).text().not_null())
+                    .col(ColumnDef::new(WordnetSenses::Synset).text().not_null())
+                    .col(ColumnDef::new(WordnetSenses::WordNum).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordnetPointers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WordnetPointers::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(WordnetPointers::Synset).text().not_null())
+                    // 0 means a semantic relation between synsets
+                    .col(ColumnDef::new(WordnetPointers::Source).integer().not_null())
+                    .col(ColumnDef::new(WordnetPointers::Relation).text().not_null())
+                    .col(
+                        ColumnDef::new(WordnetPointers::TargetSynset)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WordnetPointers::Target).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wordnet_senses_lemma")
+                    .table(WordnetSenses::Table)
+                    .col(WordnetSenses::Lemma)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wordnet_senses_synset")
+                    .table(WordnetSenses::Table)
+                    .col(WordnetSenses::Synset)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wordnet_pointers_synset")
+                    .table(WordnetPointers::Table)
+                    .col(WordnetPointers::Synset)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WordnetPointers::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WordnetSenses::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WordnetSynsets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum WordnetSynsets {
+    Table,
+    Id,
+    Synset,
+    Pos,
+    Gloss,
+}
+
+#[derive(Iden)]
+pub enum WordnetSenses {
+    Table,
+    Id,
+    Lemma,
+    Word,
+    Synset,
+    WordNum,
+}
+
+#[derive(Iden)]
+pub enum WordnetPointers {
+    Table,
+    Id,
+    Synset,
+    Source,
+    Relation,
+    TargetSynset,
+    Target,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Cedict::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Cedict::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Cedict::Traditional).text().not_null())
+                    .col(ColumnDef::new(Cedict::Simplified).text().not_null())
+                    // numbered pinyin as in the source file, e.g. `ji1 dan4`
+                    .col(ColumnDef::new(Cedict::Pinyin).text().not_null())
+                    // json array of glosses
+                    .col(ColumnDef::new(Cedict::Definitions).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cedict_simplified")
+                    .table(Cedict::Table)
+                    .col(Cedict::Simplified)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cedict_traditional")
+                    .table(Cedict::Table)
+                    .col(Cedict::Traditional)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Cedict::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Cedict {
+    Table,
+    Id,
+    Traditional,
+    Simplified,
+    Pinyin,
+    Definitions,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Full-text index over `words.translation` and `words.definition` for reverse lookups.
+/// The trigram tokenizer matches substrings, so Chinese meanings without separators work.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS words_fts USING fts5(
+                translation, definition,
+                content='words', content_rowid='id', tokenize='trigram'
+            )",
+        )
+        .await?;
+
+        // keep the index in sync with `words`
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS words_fts_ai AFTER INSERT ON words BEGIN
+                INSERT INTO words_fts(rowid, translation, definition)
+                VALUES (new.id, new.translation, new.definition);
+            END",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS words_fts_ad AFTER DELETE ON words BEGIN
+                INSERT INTO words_fts(words_fts, rowid, translation, definition)
+                VALUES ('delete', old.id, old.translation, old.definition);
+            END",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS words_fts_au
+                AFTER UPDATE OF translation, definition ON words BEGIN
+                INSERT INTO words_fts(words_fts, rowid, translation, definition)
+                VALUES ('delete', old.id, old.translation, old.definition);
+                INSERT INTO words_fts(rowid, translation, definition)
+                VALUES (new.id, new.translation, new.definition);
+            END",
+        )
+        .await?;
+
+        // index rows imported before this migration
+        db.execute_unprepared("INSERT INTO words_fts(words_fts) VALUES ('rebuild')")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TRIGGER IF EXISTS words_fts_ai")
+            .await?;
+        db.execute_unprepared("DROP TRIGGER IF EXISTS words_fts_ad")
+            .await?;
+        db.execute_unprepared("DROP TRIGGER IF EXISTS words_fts_au")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS words_fts")
+            .await?;
+
+        Ok(())
+    }
+}
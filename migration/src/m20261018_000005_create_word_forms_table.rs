@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Inverted inflection index of ECDICT, filled from `words.exchange` at import time.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordForms::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WordForms::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    // inflected form, e.g. `went`
+                    .col(ColumnDef::new(WordForms::Form).text().not_null())
+                    // its lemma, e.g. `go`
+                    .col(ColumnDef::new(WordForms::Lemma).text().not_null())
+                    // exchange type letters, e.g. `p` or `pd`
+                    .col(ColumnDef::new(WordForms::Kind).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_word_forms_form")
+                    .table(WordForms::Table)
+                    .col(WordForms::Form)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WordForms::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum WordForms {
+    Table,
+    Id,
+    Form,
+    Lemma,
+    Kind,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::{prelude::*, sea_orm::Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Characters dropped from `words.sw`, by the same tests as `ecdict::strip_word`. No
+/// whitespace character is above U+3000.
+fn stripped() -> impl Iterator<Item = char> {
+    (0..=0x3000)
+        .filter_map(char::from_u32)
+        .filter(|c| c.is_ascii_punctuation() || c.is_whitespace())
+}
+
+/// Indexes `words.word` for exact and case-insensitive lookups, unique so imports can
+/// upsert, and adds `sw`, the lower-case headword without punctuation or spaces, so
+/// `email` finds `e-mail`. Databases with duplicate headwords, left by re-running the old
+/// importer, aren't migrated, they have to be imported again.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let duplicates = db
+            .query_one_raw(Statement::from_string(
+                manager.get_database_backend(),
+                "SELECT COUNT(*) FROM (SELECT word FROM words GROUP BY word HAVING COUNT(*) > 1)",
+            ))
+            .await?
+            .map(|row| row.try_get_by_index::<i64>(0))
+            .transpose()?
+            .unwrap_or(0);
+        if duplicates > 0 {
+            return Err(DbErr::Migration(format!(
+                "{duplicates} headwords are duplicated in the words table, delete the database \
+                 and import ECDICT again"
+            )));
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Words::Table)
+                    .add_column(ColumnDef::new(Words::Sw).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut expr = "word".to_string();
+        for c in stripped() {
+            let c = if c == '\'' {
+                "''".to_string()
+            } else {
+                c.to_string()
+            };
+            expr = format!("REPLACE({expr}, '{c}', '')");
+        }
+        db.execute_unprepared(&format!("UPDATE words SET sw = LOWER({expr})"))
+            .await?;
+
+        db.execute_unprepared("CREATE UNIQUE INDEX IF NOT EXISTS idx_words_word ON words (word)")
+            .await?;
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_words_word_nocase ON words (word COLLATE NOCASE)",
+        )
+        .await?;
+        db.execute_unprepared("CREATE INDEX IF NOT EXISTS idx_words_sw ON words (sw)")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_words_sw")
+            .await?;
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_words_word_nocase")
+            .await?;
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_words_word")
+            .await?;
+        db.execute_unprepared("ALTER TABLE words DROP COLUMN sw")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum Words {
+    Table,
+    Sw,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Records imported files by checksum so an unchanged file is skipped and an interrupted
+/// one resumed.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EcdictImports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EcdictImports::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(EcdictImports::Path).text().not_null())
+                    // sha256 of the file
+                    .col(ColumnDef::new(EcdictImports::Checksum).text().not_null())
+                    // records committed so far, where an interrupted import resumes
+                    .col(ColumnDef::new(EcdictImports::Rows).integer().not_null())
+                    .col(ColumnDef::new(EcdictImports::Finished).boolean().not_null())
+                    .col(ColumnDef::new(EcdictImports::UpdatedAt).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ecdict_imports_checksum")
+                    .table(EcdictImports::Table)
+                    .col(EcdictImports::Checksum)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EcdictImports::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum EcdictImports {
+    Table,
+    Id,
+    Path,
+    Checksum,
+    Rows,
+    Finished,
+    UpdatedAt,
+}
@@ -0,0 +1,196 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tables of ECDICT's companion datasets: `lemma.en.txt`, `resemble.txt` and `wordroot.txt`.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EcdictLemmas::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EcdictLemmas::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(EcdictLemmas::Form).text().not_null())
+                    .col(ColumnDef::new(EcdictLemmas::Lemma).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ecdict_lemmas_form")
+                    .table(EcdictLemmas::Table)
+                    .col(EcdictLemmas::Form)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ResembleGroups::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ResembleGroups::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    // json array of the words discriminated
+                    .col(ColumnDef::new(ResembleGroups::Words).text().not_null())
+                    // usage notes, in Chinese
+                    .col(ColumnDef::new(ResembleGroups::Content).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ResembleWords::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ResembleWords::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(ResembleWords::Word).text().not_null())
+                    .col(ColumnDef::new(ResembleWords::GroupId).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_resemble_words_word")
+                    .table(ResembleWords::Table)
+                    .col(ResembleWords::Word)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Wordroots::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Wordroots::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Wordroots::Root).text().not_null())
+                    // prefix, suffix or root
+                    .col(ColumnDef::new(Wordroots::Class).text())
+                    .col(ColumnDef::new(Wordroots::Origin).text())
+                    .col(ColumnDef::new(Wordroots::Meaning).text())
+                    // json array of example words
+                    .col(ColumnDef::new(Wordroots::Examples).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WordrootWords::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WordrootWords::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(WordrootWords::Word).text().not_null())
+                    .col(ColumnDef::new(WordrootWords::RootId).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wordroot_words_word")
+                    .table(WordrootWords::Table)
+                    .col(WordrootWords::Word)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WordrootWords::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Wordroots::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ResembleWords::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ResembleGroups::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(EcdictLemmas::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum EcdictLemmas {
+    Table,
+    Id,
+    Form,
+    Lemma,
+}
+
+#[derive(Iden)]
+pub enum ResembleGroups {
+    Table,
+    Id,
+    Words,
+    Content,
+}
+
+#[derive(Iden)]
+pub enum ResembleWords {
+    Table,
+    Id,
+    Word,
+    GroupId,
+}
+
+#[derive(Iden)]
+pub enum Wordroots {
+    Table,
+    Id,
+    Root,
+    Class,
+    Origin,
+    Meaning,
+    Examples,
+}
+
+#[derive(Iden)]
+pub enum WordrootWords {
+    Table,
+    Id,
+    Word,
+    RootId,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Query results of online translators, one row per
+/// `(translator, source, target, normalized query)`, looked up by `key_hash`, their SHA-256.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Cache::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Cache::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Cache::KeyHash).text().not_null())
+                    .col(ColumnDef::new(Cache::Translator).text().not_null())
+                    .col(ColumnDef::new(Cache::Source).text().not_null())
+                    .col(ColumnDef::new(Cache::Target).text().not_null())
+                    .col(ColumnDef::new(Cache::Query).text().not_null())
+                    // serialized response
+                    .col(ColumnDef::new(Cache::Payload).text().not_null())
+                    // unix seconds
+                    .col(ColumnDef::new(Cache::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Cache::LastHitAt).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Cache::HitCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cache_key_hash")
+                    .table(Cache::Table)
+                    .col(Cache::KeyHash)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cache_created_at")
+                    .table(Cache::Table)
+                    .col(Cache::CreatedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // least recently used entries are evicted first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_cache_last_hit_at")
+                    .table(Cache::Table)
+                    .col(Cache::LastHitAt)
+                    .col(Cache::Id)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Cache::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+pub enum Cache {
+    Table,
+    Id,
+    KeyHash,
+    Translator,
+    Source,
+    Target,
+    Query,
+    Payload,
+    CreatedAt,
+    LastHitAt,
+    HitCount,
+}
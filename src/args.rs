@@ -1,6 +1,21 @@
+use std::path::PathBuf;
+
 use clap::{Args, Parser, Subcommand};
 
-use crate::translators::QueryArgs;
+use crate::{
+    cache::{
+        archive::Conflict,
+        cmd::{CacheFilter, EntryArgs, ShowArgs},
+    },
+    translators::{
+        ecdict::{
+            import::Dataset,
+            random::{RandomArgs, WotdArgs},
+            search::SearchArgs,
+        },
+        QueryArgs,
+    },
+};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -18,8 +33,17 @@ pub enum Commands {
     /// Manage cache
     Cache(CacheArgs),
 
+    /// Query a DICT (RFC 2229) server
+    Dict(DictArgs),
+
+    /// Explore the offline ECDICT word list
+    Ecdict(EcdictArgs),
+
     /// Default command
     Query(QueryArgs),
+
+    /// Word of the day from ECDICT, filtered like `ecdict random`
+    Wotd(WotdArgs),
 }
 
 #[derive(Debug, Args)]
@@ -34,13 +58,133 @@ pub struct CacheArgs {
 
 #[derive(Debug, Subcommand)]
 pub enum CacheCommands {
-    /// Clean cache
-    Clean,
+    /// Clean cache, or only the entries matching the filters
+    Clean(CacheFilter),
 
     /// Show cache, alias: list
-    #[command(alias="list")]
-    Show,
+    #[command(alias = "list")]
+    Show(ShowArgs),
 
     /// Remove expired cache
     Purge,
+
+    /// Print one cached result and its age
+    Get(EntryArgs),
+
+    /// Remove one cached result
+    Rm(EntryArgs),
+
+    /// Query the translator again and replace one cached result
+    Refresh(EntryArgs),
+
+    /// Write valid entries to a JSON lines archive, to be imported on another machine
+    Export {
+        /// [path] Archive file, compressed with zstd if it ends with `.zst`
+        file: PathBuf,
+
+        /// [bool] Compress with zstd whatever the file name
+        #[arg(short, long)]
+        zstd: bool,
+    },
+
+    /// Merge an archive written by `cache export`
+    Import {
+        /// [path] Archive file, plain or zstd-compressed
+        file: PathBuf,
+
+        /// [enum] Entry kept when both have a query
+        #[arg(short, long, value_enum, default_value = "newest")]
+        on_conflict: Conflict,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct DictArgs {
+    #[command(subcommand)]
+    pub commands: DictCommands,
+
+    /// [bool] Print debug details
+    #[arg(short = 'v', long, env = "RUNSLATE_VERBOSE")]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DictCommands {
+    /// Show databases on the server, alias: db
+    #[command(alias = "db")]
+    Databases,
+
+    /// Show match strategies on the server, alias: strat
+    #[command(alias = "strat")]
+    Strategies,
+
+    /// Match words with a strategy
+    Match {
+        /// [string] Database to search, `*` for all
+        #[arg(short, long, default_value = "*", env = "RUNSLATE_DICT_DATABASE")]
+        database: String,
+
+        /// [string] Match strategy, e.g. prefix, soundex, lev
+        #[arg(short, long, default_value = "lev", env = "RUNSLATE_DICT_STRATEGY")]
+        strategy: String,
+
+        /// [strings] Words to match
+        #[arg(num_args = 1.., required = true)]
+        words: Vec<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct EcdictArgs {
+    #[command(subcommand)]
+    pub commands: EcdictCommands,
+
+    /// [bool] Print debug details
+    #[arg(short = 'v', long, env = "RUNSLATE_VERBOSE")]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EcdictCommands {
+    /// Import ECDICT's stardict.csv, lemma.en.txt, resemble.txt or wordroot.txt
+    Import {
+        /// [path] ECDICT csv file or companion dataset
+        #[arg(default_value = "stardict.csv")]
+        path: PathBuf,
+
+        /// [enum] Dataset of the file, told by file name by default
+        #[arg(short, long, value_enum)]
+        dataset: Option<Dataset>,
+
+        /// [bool] Import even if the file is unchanged since the last import
+        #[arg(short, long)]
+        force: bool,
+
+        /// [bool] Rebuild the FST index afterwards, always done with `RUNSLATE_ECDICT_ENGINE=fst`
+        #[arg(short, long)]
+        index: bool,
+    },
+
+    /// Build the FST index used by `RUNSLATE_ECDICT_ENGINE=fst`
+    Index,
+
+    /// Complete a headword prefix from the FST index
+    Complete {
+        /// [string] Prefix to complete
+        prefix: String,
+
+        /// [number] Max words
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// [bool] Words within one or two edits instead of by prefix
+        #[arg(short, long)]
+        fuzzy: bool,
+    },
+
+    /// Search headwords by pattern, length, tags and frequency
+    Search(SearchArgs),
+
+    /// Random words filtered by tags, level and frequency
+    Random(RandomArgs),
 }
@@ -0,0 +1,68 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use dotenvy::dotenv;
+use sea_orm::{ActiveValue::Set, DatabaseTransaction, EntityTrait, TransactionTrait};
+
+use runslate::translators::cedict::{
+    entities::cedict::{ActiveModel as CedictEntry, Entity as CedictEntity},
+    parse_line,
+};
+use runslate::translators::ecdict::connect_db_writable;
+
+/// Import the CC-CEDICT text file, e.g. `cargo run --bin cedict -- cedict_ts.u8`
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+
+    let cedict_path = std::env::args()
+        .nth(1)
+        .ok_or(anyhow::anyhow!("Usage: cedict <cedict_ts.u8>"))?;
+    let db = connect_db_writable().await?;
+
+    // replace an earlier import as a whole, which is kept if this one fails
+    let txn = db.begin().await?;
+    CedictEntity::delete_many().exec(&txn).await?;
+    import_cedict(&txn, &cedict_path).await?;
+    txn.commit().await?;
+    println!("Database migration finished!");
+
+    Ok(())
+}
+
+async fn import_cedict(txn: &DatabaseTransaction, cedict_path: &str) -> anyhow::Result<()> {
+    let reader = BufReader::new(File::open(cedict_path)?);
+    let mut count = 0u32;
+    let mut batch: Vec<CedictEntry> = Vec::with_capacity(1000);
+    for line in reader.lines() {
+        let Some(entry) = parse_line(&line?) else {
+            continue;
+        };
+        batch.push(CedictEntry {
+            traditional: Set(entry.traditional),
+            simplified: Set(entry.simplified),
+            pinyin: Set(entry.pinyin),
+            definitions: Set(serde_json::to_string(&entry.definitions)?),
+            ..Default::default()
+        });
+        if batch.len() >= 1000 {
+            count += insert_batch(txn, &mut batch).await?;
+        }
+    }
+    if !batch.is_empty() {
+        count += insert_batch(txn, &mut batch).await?;
+    }
+    println!("Imported {} records into cedict table.", count);
+    Ok(())
+}
+
+async fn insert_batch(
+    txn: &DatabaseTransaction,
+    batch: &mut Vec<CedictEntry>,
+) -> anyhow::Result<u32> {
+    let len = batch.len() as u32;
+    CedictEntity::insert_many(batch.drain(..)).exec(txn).await?;
+    Ok(len)
+}
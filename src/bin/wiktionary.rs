@@ -0,0 +1,254 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use dotenvy::dotenv;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use runslate::translators::ecdict::connect_db_writable;
+use runslate::translators::wiktionary::entities::wiktionary::{
+    ActiveModel as WiktionaryEntry, Column as WiktionaryColumn, Entity as WiktionaryEntity,
+};
+
+/// Import a kaikki.org (wiktextract) JSONL dump, e.g.
+/// `cargo run --bin wiktionary -- kaikki.org-dictionary-English.jsonl`
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+
+    let jsonl_path = std::env::args()
+        .nth(1)
+        .ok_or(anyhow::anyhow!("Usage: wiktionary <kaikki.jsonl>"))?;
+    let db = connect_db_writable().await?;
+
+    import_jsonl(&db, &jsonl_path).await?;
+    println!("Database migration finished!");
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct KaikkiEntry {
+    word: String,
+    lang: String,
+    lang_code: String,
+    pos: Option<String>,
+    etymology_text: Option<String>,
+    #[serde(default)]
+    senses: Vec<KaikkiSense>,
+    #[serde(default)]
+    forms: Vec<Form>,
+    #[serde(default)]
+    sounds: Vec<KaikkiSound>,
+    #[serde(default)]
+    translations: Vec<Translation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KaikkiSense {
+    #[serde(default)]
+    glosses: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    examples: Vec<KaikkiExample>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KaikkiExample {
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KaikkiSound {
+    ipa: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Sense as stored in `wiktionary.senses`.
+#[derive(Debug, Serialize)]
+struct Sense {
+    glosses: Vec<String>,
+    tags: Vec<String>,
+    examples: Vec<String>,
+}
+
+/// Form as stored in `wiktionary.forms`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Form {
+    form: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Pronunciation as stored in `wiktionary.sounds`.
+#[derive(Debug, Serialize)]
+struct Sound {
+    ipa: String,
+    tags: Vec<String>,
+}
+
+/// Translation as stored in `wiktionary.translations`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Translation {
+    #[serde(default)]
+    lang: String,
+    // older dumps use `code`, newer ones `lang_code`
+    #[serde(default, alias = "lang_code")]
+    code: String,
+    #[serde(default)]
+    word: String,
+    sense: Option<String>,
+}
+
+impl KaikkiEntry {
+    fn into_model(self) -> anyhow::Result<WiktionaryEntry> {
+        let senses: Vec<Sense> = self
+            .senses
+            .into_iter()
+            .filter(|s| !s.glosses.is_empty())
+            .map(|s| Sense {
+                glosses: s.glosses,
+                tags: s.tags,
+                examples: s.examples.into_iter().filter_map(|e| e.text).collect(),
+            })
+            .collect();
+        let sounds: Vec<Sound> = self
+            .sounds
+            .into_iter()
+            .filter_map(|s| {
+                Some(Sound {
+                    ipa: s.ipa?,
+                    tags: s.tags,
+                })
+            })
+            .collect();
+        let translations: Vec<Translation> = self
+            .translations
+            .into_iter()
+            .filter(|t| !t.word.is_empty())
+            .collect();
+
+        Ok(WiktionaryEntry {
+            word: Set(self.word),
+            lang: Set(self.lang),
+            lang_code: Set(self.lang_code),
+            pos: Set(self.pos),
+            etymology: Set(self.etymology_text),
+            senses: Set(Some(serde_json::to_string(&senses)?)),
+            forms: Set(Some(serde_json::to_string(&self.forms)?)),
+            sounds: Set(Some(serde_json::to_string(&sounds)?)),
+            translations: Set(Some(serde_json::to_string(&translations)?)),
+            ..Default::default()
+        })
+    }
+}
+
+/// Import `jsonl_path` in one transaction. Entries of a language found in the dump replace the
+/// ones imported before, so importing a dump again doesn't duplicate them.
+async fn import_jsonl(db: &DatabaseConnection, jsonl_path: &str) -> anyhow::Result<()> {
+    let reader = BufReader::new(File::open(jsonl_path)?);
+    let mut count = 0u32;
+    let mut skipped = 0u32;
+    let mut cleared: HashSet<String> = HashSet::new();
+    let mut batch: Vec<WiktionaryEntry> = Vec::with_capacity(1000);
+    let txn = db.begin().await?;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // redirects and other non-entry lines have no `pos`/`senses`, skip what fails to parse
+        let Ok(entry) = serde_json::from_str::<KaikkiEntry>(&line) else {
+            skipped += 1;
+            continue;
+        };
+        if cleared.insert(entry.lang_code.clone()) {
+            let removed = WiktionaryEntity::delete_many()
+                .filter(WiktionaryColumn::LangCode.eq(&entry.lang_code))
+                .exec(&txn)
+                .await?
+                .rows_affected;
+            if removed > 0 {
+                println!("Replacing {} {} records.", removed, entry.lang);
+            }
+        }
+        batch.push(entry.into_model()?);
+        if batch.len() >= 1000 {
+            count += insert_batch(&txn, &mut batch).await?;
+        }
+    }
+    if !batch.is_empty() {
+        count += insert_batch(&txn, &mut batch).await?;
+    }
+    txn.commit().await?;
+    println!(
+        "Imported {} records into wiktionary table, {} lines skipped.",
+        count, skipped
+    );
+    Ok(())
+}
+
+async fn insert_batch(
+    txn: &DatabaseTransaction,
+    batch: &mut Vec<WiktionaryEntry>,
+) -> anyhow::Result<u32> {
+    let len = batch.len() as u32;
+    WiktionaryEntity::insert_many(batch.drain(..))
+        .exec(txn)
+        .await?;
+    Ok(len)
+}
+
+#[cfg(test)]
+mod test {
+    use sea_orm::ActiveValue::Set;
+
+    use super::KaikkiEntry;
+
+    #[test]
+    fn test_parse_kaikki() {
+        let line = r#"{"word": "dog", "lang": "English", "lang_code": "en", "pos": "noun", "etymology_text": "From Middle English dogge.", "senses": [{"glosses": ["A mammal."], "tags": ["countable"], "examples": [{"text": "The dog barked."}, {"ref": "no text"}]}, {"tags": ["no-gloss"]}], "forms": [{"form": "dogs", "tags": ["plural"]}], "sounds": [{"ipa": "/dɒɡ/", "tags": ["UK"]}, {"audio": "dog.ogg"}], "translations": [{"lang": "Chinese Mandarin", "code": "cmn", "word": "狗", "sense": "animal"}, {"lang": "French", "lang_code": "fr", "word": "chien"}, {"lang": "German", "code": "de"}]}"#;
+        let model = serde_json::from_str::<KaikkiEntry>(line)
+            .unwrap()
+            .into_model()
+            .unwrap();
+        assert_eq!(model.word, Set("dog".to_string()));
+        assert_eq!(model.lang_code, Set("en".to_string()));
+        assert_eq!(model.pos, Set(Some("noun".to_string())));
+        assert_eq!(
+            model.senses,
+            Set(Some(
+                r#"[{"glosses":["A mammal."],"tags":["countable"],"examples":["The dog barked."]}]"#
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            model.forms,
+            Set(Some(r#"[{"form":"dogs","tags":["plural"]}]"#.to_string()))
+        );
+        assert_eq!(
+            model.sounds,
+            Set(Some(r#"[{"ipa":"/dɒɡ/","tags":["UK"]}]"#.to_string()))
+        );
+        assert_eq!(
+            model.translations,
+            Set(Some(
+                r#"[{"lang":"Chinese Mandarin","code":"cmn","word":"狗","sense":"animal"},{"lang":"French","code":"fr","word":"chien","sense":null}]"#
+                    .to_string()
+            ))
+        );
+
+        // redirects have no `lang_code`
+        assert!(
+            serde_json::from_str::<KaikkiEntry>(r#"{"word": "dogge", "redirect": "dog"}"#).is_err()
+        );
+    }
+}
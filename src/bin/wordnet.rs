@@ -0,0 +1,113 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use dotenvy::dotenv;
+use sea_orm::{ActiveValue::Set, DatabaseTransaction, EntityTrait, TransactionTrait};
+
+use runslate::translators::ecdict::connect_db_writable;
+use runslate::translators::wordnet::{
+    entities::{wordnet_pointers, wordnet_senses, wordnet_synsets},
+    wndb::{lemma_key, parse_data_line, DataLine, DATA_FILES},
+};
+
+/// Import Princeton WordNet database files, e.g.
+/// `cargo run --bin wordnet -- WordNet-3.0/dict`
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+
+    let dict_dir = std::env::args()
+        .nth(1)
+        .ok_or(anyhow::anyhow!("Usage: wordnet <wordnet dict dir>"))?;
+    let db = connect_db_writable().await?;
+
+    // replace an earlier import as a whole, which is kept if this one fails
+    let txn = db.begin().await?;
+    wordnet_pointers::Entity::delete_many().exec(&txn).await?;
+    wordnet_senses::Entity::delete_many().exec(&txn).await?;
+    wordnet_synsets::Entity::delete_many().exec(&txn).await?;
+    for (file_name, _) in DATA_FILES {
+        let path = Path::new(&dict_dir).join(file_name);
+        if !path.exists() {
+            println!("{} not found, skipped.", path.display());
+            continue;
+        }
+        import_data_file(&txn, &path).await?;
+    }
+    txn.commit().await?;
+    println!("Database migration finished!");
+
+    Ok(())
+}
+
+async fn import_data_file(txn: &DatabaseTransaction, path: &Path) -> anyhow::Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut count = 0u32;
+    let mut batch: Vec<DataLine> = Vec::with_capacity(1000);
+    for line in reader.lines() {
+        if let Some(data) = parse_data_line(&line?) {
+            batch.push(data);
+        }
+        if batch.len() >= 1000 {
+            count += insert_batch(txn, &mut batch).await?;
+        }
+    }
+    if !batch.is_empty() {
+        count += insert_batch(txn, &mut batch).await?;
+    }
+    println!("Imported {} synsets from {}.", count, path.display());
+    Ok(())
+}
+
+async fn insert_batch(txn: &DatabaseTransaction, batch: &mut Vec<DataLine>) -> anyhow::Result<u32> {
+    let len = batch.len() as u32;
+    let mut synsets = vec![];
+    let mut senses = vec![];
+    let mut pointers = vec![];
+    for data in batch.drain(..) {
+        for (idx, word) in data.words.iter().enumerate() {
+            senses.push(wordnet_senses::ActiveModel {
+                lemma: Set(lemma_key(word)),
+                word: Set(word.clone()),
+                synset: Set(data.synset.clone()),
+                word_num: Set(idx as i32 + 1),
+                ..Default::default()
+            });
+        }
+        for pointer in data.pointers {
+            pointers.push(wordnet_pointers::ActiveModel {
+                synset: Set(data.synset.clone()),
+                source: Set(pointer.source),
+                relation: Set(pointer.relation),
+                target_synset: Set(pointer.target_synset),
+                target: Set(pointer.target),
+                ..Default::default()
+            });
+        }
+        synsets.push(wordnet_synsets::ActiveModel {
+            synset: Set(data.synset),
+            pos: Set(data.pos),
+            gloss: Set(Some(data.gloss)),
+            ..Default::default()
+        });
+    }
+
+    wordnet_synsets::Entity::insert_many(synsets)
+        .exec(txn)
+        .await?;
+    // keep statements below SQLite's bound parameter limit
+    for chunk in senses.chunks(1000) {
+        wordnet_senses::Entity::insert_many(chunk.to_vec())
+            .exec(txn)
+            .await?;
+    }
+    for chunk in pointers.chunks(1000) {
+        wordnet_pointers::Entity::insert_many(chunk.to_vec())
+            .exec(txn)
+            .await?;
+    }
+    Ok(len)
+}
@@ -0,0 +1,323 @@
+//! `runslate cache export` and `runslate cache import`: cache entries as JSON lines, one entry
+//! per line, zstd-compressed when the file name ends with `.zst`.
+//!
+//! Entries keep their `created_at`, so they expire on the other machine when they would have
+//! on this one.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use log::debug;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+use super::{
+    cmd::{select, CacheFilter},
+    entities::cache,
+    evict, normalize,
+    util::{cache_time, connect, now, Limits},
+    CacheKey,
+};
+
+/// First bytes of a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+const ZSTD_LEVEL: i32 = 9;
+
+/// Which entry is kept when the archive and the cache both have a key.
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum Conflict {
+    /// The one created later
+    Newest,
+    /// The one in the cache
+    Existing,
+    /// The one in the archive
+    Incoming,
+}
+
+/// One line of an archive.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub translator: String,
+    pub source: String,
+    pub target: String,
+    pub query: String,
+    /// Serialized response, as cached
+    pub payload: String,
+    pub created_at: i64,
+    pub last_hit_at: i64,
+    pub hit_count: i32,
+}
+
+impl From<cache::Model> for ArchiveEntry {
+    fn from(entry: cache::Model) -> Self {
+        ArchiveEntry {
+            translator: entry.translator,
+            source: entry.source,
+            target: entry.target,
+            query: entry.query,
+            payload: entry.payload,
+            created_at: entry.created_at,
+            last_hit_at: entry.last_hit_at,
+            hit_count: entry.hit_count,
+        }
+    }
+}
+
+/// What an import did.
+#[derive(Debug, Default, PartialEq)]
+pub struct Merged {
+    pub added: u64,
+    pub replaced: u64,
+    pub kept: u64,
+}
+
+/// Write the valid cache entries to `path`, zstd-compressed if asked or named `*.zst`.
+pub async fn export(path: &Path, zstd: bool) -> Result<(), Error> {
+    let db = connect().await?;
+    let filter = CacheFilter {
+        valid: true,
+        ..Default::default()
+    };
+    let entries = select(&db, &filter, now(), cache_time).await?;
+    let count = entries.len();
+
+    let mut file = BufWriter::new(File::create(path)?);
+    if zstd || path.extension().is_some_and(|ext| ext == "zst") {
+        let mut encoder = zstd::Encoder::new(&mut file, ZSTD_LEVEL)?;
+        write_entries(&mut encoder, entries)?;
+        encoder.finish()?;
+    } else {
+        write_entries(&mut file, entries)?;
+    }
+    file.flush()?;
+    println!("Exported {} entries into {}.", count, path.display());
+    Ok(())
+}
+
+/// Merge the entries of the archive at `path` into the cache.
+pub async fn import(path: &Path, conflict: &Conflict) -> Result<(), Error> {
+    let entries = read_entries(path)?;
+    let sum = entries.len();
+    let db = connect().await?;
+    let merged = merge(&db, entries, conflict).await?;
+    evict(&db, &Limits::from_env()).await?;
+    println!(
+        "Imported {} entries from {}: {} added, {} replaced, {} kept.",
+        sum,
+        path.display(),
+        merged.added,
+        merged.replaced,
+        merged.kept
+    );
+    Ok(())
+}
+
+fn write_entries(writer: &mut impl Write, entries: Vec<cache::Model>) -> Result<(), Error> {
+    for entry in entries {
+        serde_json::to_writer(&mut *writer, &ArchiveEntry::from(entry))?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Entries of a plain or zstd-compressed archive, told apart by the first bytes.
+fn read_entries(path: &Path) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut file = BufReader::new(File::open(path)?);
+    let compressed = file.fill_buf()?.starts_with(&ZSTD_MAGIC);
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(zstd::Decoder::with_buffer(file)?)
+    } else {
+        Box::new(file)
+    };
+
+    let mut entries = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str::<ArchiveEntry>(&line).map_err(|e| {
+            Error::DeserializeFailed(format!("{}:{}: {}", path.display(), i + 1, e))
+        })?;
+        entries.push(entry);
+    }
+    debug!("Read {} entries from {}", entries.len(), path.display());
+    Ok(entries)
+}
+
+/// Add `entries` to the cache, resolving keys it already has by `conflict`.
+pub async fn merge(
+    db: &DatabaseConnection,
+    entries: Vec<ArchiveEntry>,
+    conflict: &Conflict,
+) -> Result<Merged, Error> {
+    let mut merged = Merged::default();
+    let txn = db.begin().await?;
+    for entry in entries {
+        let key = CacheKey {
+            translator: entry.translator,
+            source: entry.source,
+            target: entry.target,
+            query: normalize(&entry.query),
+        };
+        let key_hash = key.hash();
+        let existing = cache::Entity::find()
+            .filter(cache::Column::KeyHash.eq(&key_hash))
+            .one(&txn)
+            .await?;
+
+        let Some(existing) = existing else {
+            cache::ActiveModel {
+                translator: Set(key.translator),
+                source: Set(key.source),
+                target: Set(key.target),
+                query: Set(key.query),
+                payload: Set(entry.payload),
+                created_at: Set(entry.created_at),
+                last_hit_at: Set(entry.last_hit_at),
+                hit_count: Set(entry.hit_count),
+                key_hash: Set(key_hash),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            merged.added += 1;
+            continue;
+        };
+
+        let replace = match conflict {
+            Conflict::Newest => entry.created_at > existing.created_at,
+            Conflict::Existing => false,
+            Conflict::Incoming => true,
+        };
+        if !replace {
+            merged.kept += 1;
+            continue;
+        }
+        let last_hit_at = existing.last_hit_at.max(entry.last_hit_at);
+        let mut existing = existing.into_active_model();
+        existing.payload = Set(entry.payload);
+        existing.created_at = Set(entry.created_at);
+        existing.last_hit_at = Set(last_hit_at);
+        existing.update(&txn).await?;
+        merged.replaced += 1;
+    }
+    txn.commit().await?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{CacheMigrator, MigratorTrait};
+    use sea_orm::{Database, EntityTrait};
+
+    use super::{merge, read_entries, write_entries, ArchiveEntry, Conflict, Merged};
+    use crate::{
+        cache::{entities::cache, put, CacheKey},
+        translators::Lang,
+    };
+
+    #[tokio::test]
+    async fn test_archive() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        CacheMigrator::up(&db, None).await.unwrap();
+        let key = |query| CacheKey::new(query, &Lang::En, &Lang::Zh, "google");
+        put(&db, &key("hello"), "\"你好\"".to_string(), 100)
+            .await
+            .unwrap();
+        put(&db, &key("world"), "\"世界\"".to_string(), 100)
+            .await
+            .unwrap();
+
+        // round trip through a compressed file
+        let path = std::env::temp_dir().join(format!(
+            "runslate_test_cache_{}.jsonl.zst",
+            std::process::id()
+        ));
+        let entries = cache::Entity::find().all(&db).await.unwrap();
+        let mut encoder = zstd::Encoder::new(std::fs::File::create(&path).unwrap(), 0).unwrap();
+        write_entries(&mut encoder, entries.clone()).unwrap();
+        encoder.finish().unwrap();
+        let archived = read_entries(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            archived,
+            entries
+                .into_iter()
+                .map(ArchiveEntry::from)
+                .collect::<Vec<_>>()
+        );
+
+        let incoming = |query: &str, payload: &str, created_at| ArchiveEntry {
+            translator: "google".to_string(),
+            source: "En".to_string(),
+            target: "Zh".to_string(),
+            query: query.to_string(),
+            payload: payload.to_string(),
+            created_at,
+            last_hit_at: created_at,
+            hit_count: 0,
+        };
+        let merged = merge(
+            &db,
+            vec![
+                incoming("hello", "\"哈喽\"", 200),
+                incoming("world", "\"世間\"", 50),
+                incoming("good", "\"好\"", 50),
+            ],
+            &Conflict::Newest,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            merged,
+            Merged {
+                added: 1,
+                replaced: 1,
+                kept: 1
+            }
+        );
+        let payloads = cache::Entity::find()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.query, m.payload, m.created_at))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            payloads,
+            vec![
+                ("hello".into(), "\"哈喽\"".into(), 200),
+                ("world".into(), "\"世界\"".into(), 100),
+                ("good".into(), "\"好\"".into(), 50),
+            ]
+        );
+
+        let merged = merge(
+            &db,
+            vec![incoming("world", "\"世間\"", 50)],
+            &Conflict::Existing,
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.kept, 1);
+        let merged = merge(
+            &db,
+            vec![incoming("world", "\"世間\"", 50)],
+            &Conflict::Incoming,
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.replaced, 1);
+    }
+}
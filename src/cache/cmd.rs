@@ -1,101 +1,545 @@
-use std::{
-    cmp,
-    fs::{self, DirEntry},
-};
+//! `runslate cache` subcommands.
+
+use std::cmp::Reverse;
 
+use clap::{Args, ValueEnum};
 use log::info;
+use regex::Regex;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Serialize;
 
 use crate::{
-    cache::util::unpack,
-    utils::file::{fmt_size, iter_dir},
+    errors::Error,
+    translators::{Lang, TranslatorArgs},
+    utils::{
+        dyer::{Colors, Dye},
+        file::fmt_size,
+    },
 };
 
-/// List all cache files in `APP_DIR`.
-pub fn list() {
-    let mut s_size = 0u64;
-    let mut c_valid = 0usize;
-    let mut ff_size_len = 0usize;
-    let mut lines = vec![];
-
-    let (_, sum) = iter_dir(|de: DirEntry| {
-        let f_size = fs::metadata(de.path()).unwrap().len();
-        let ff_size = fmt_size(f_size);
-        ff_size_len = cmp::max(ff_size_len, ff_size.0.len());
-
-        let is_expired = match unpack(de.file_name()) {
-            Ok(_) => {
-                c_valid += 1;
-                String::from("✔")
+use super::{
+    entities::cache,
+    evict, find, put,
+    util::{cache_time, connect, expired, expired_before, now, Limits},
+    CacheKey,
+};
+
+#[derive(Debug, Args)]
+pub struct ShowArgs {
+    #[command(flatten)]
+    pub filter: CacheFilter,
+
+    /// [enum] Sort by, the newest, largest or most hit first
+    #[arg(long, value_enum, default_value = "age")]
+    pub sort: CacheSort,
+
+    /// [bool] Reverse the order
+    #[arg(short, long)]
+    pub reverse: bool,
+
+    /// [bool] Print entries as a JSON array
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Filters on cache entries, shared by `show` and `clean`.
+#[derive(Debug, Default, Args)]
+pub struct CacheFilter {
+    /// [strings] Translators, `http` matches every user-defined backend
+    #[arg(short, long)]
+    pub translator: Vec<String>,
+
+    /// [enum] Source language
+    #[arg(short, long)]
+    pub source_lang: Option<Lang>,
+
+    /// [enum] Target language
+    #[arg(short = 'd', long)]
+    pub target_lang: Option<Lang>,
+
+    /// [string] Query contains, case-insensitive
+    #[arg(short, long)]
+    pub query: Option<String>,
+
+    /// [regex] Query matches, e.g. `^\w+$` for single words
+    #[arg(short = 'E', long)]
+    pub regex: Option<Regex>,
+
+    /// [bool] Expired entries only
+    #[arg(long, conflicts_with = "valid")]
+    pub expired: bool,
+
+    /// [bool] Valid entries only
+    #[arg(long)]
+    pub valid: bool,
+
+    /// [duration] Cached longer ago than, e.g. `90s`, `30m`, `12h`, `7d`, `2w`
+    #[arg(long, value_parser = parse_age)]
+    pub older_than: Option<i64>,
+
+    /// [duration] Cached more recently than
+    #[arg(long, value_parser = parse_age)]
+    pub newer_than: Option<i64>,
+}
+
+impl CacheFilter {
+    /// SQL part of the filters, the rest is checked by `matches`.
+    fn condition(&self, now: i64) -> Condition {
+        let mut condition = Condition::all();
+        if !self.translator.is_empty() {
+            condition = condition.add(self.translator.iter().fold(
+                Condition::any(),
+                |any, translator| {
+                    any.add(cache::Column::Translator.eq(translator))
+                        .add(cache::Column::Translator.starts_with(format!("{translator}.")))
+                },
+            ));
+        }
+        if let Some(source) = &self.source_lang {
+            condition = condition.add(cache::Column::Source.eq(source.to_string()));
+        }
+        if let Some(target) = &self.target_lang {
+            condition = condition.add(cache::Column::Target.eq(target.to_string()));
+        }
+        if let Some(age) = self.older_than {
+            condition = condition.add(cache::Column::CreatedAt.lte(now - age));
+        }
+        if let Some(age) = self.newer_than {
+            condition = condition.add(cache::Column::CreatedAt.gt(now - age));
+        }
+        condition
+    }
+
+    fn matches(&self, entry: &cache::Model, now: i64, ttl: &impl Fn(&str) -> i64) -> bool {
+        if let Some(query) = &self.query {
+            if !entry.query.to_lowercase().contains(&query.to_lowercase()) {
+                return false;
             }
-            Err(_) => String::from("✘"),
-        };
+        }
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(&entry.query) {
+                return false;
+            }
+        }
+        let is_expired = entry.created_at <= now - ttl(&entry.translator);
+        (!self.expired || is_expired) && (!self.valid || !is_expired)
+    }
+}
 
-        lines.push((is_expired, ff_size, de.file_name()));
-        s_size += f_size;
-        true
-    });
-
-    for (is_expired, (ff_size, ff_size_u), f_name) in lines {
-        println!(
-            "{is_expired} {: <ff_size_len$}{: >2} {:}",
-            ff_size,
-            ff_size_u,
-            f_name.to_str().unwrap_or("")
-        );
+#[derive(Clone, Debug, ValueEnum)]
+pub enum CacheSort {
+    Age,
+    Size,
+    Hits,
+}
+
+/// Seconds of `90`, `90s`, `30m`, `12h`, `7d` or `2w`.
+pub fn parse_age(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let unit_at = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_at);
+    let number = number.parse::<i64>().map_err(|e| format!("{s}: {e}"))?;
+    let unit = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 86400 * 7,
+        unit => return Err(format!("{s}: unknown unit `{unit}`, use s, m, h, d or w")),
+    };
+    number
+        .checked_mul(unit)
+        .ok_or_else(|| format!("{s}: too large"))
+}
+
+/// `45s`, `12m`, `3h` or `2d`, rounded down.
+fn fmt_age(seconds: i64) -> String {
+    match seconds.max(0) {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
     }
+}
 
-    let (s_size, s_size_u) = fmt_size(s_size);
-    println!("{c_valid}/{sum} valid file(s), {s_size} {s_size_u} in total.")
+/// An entry as `cache show --json` prints it.
+#[derive(Debug, Serialize)]
+struct EntryInfo {
+    translator: String,
+    source: String,
+    target: String,
+    query: String,
+    size: u64,
+    hits: i32,
+    created_at: i64,
+    last_hit_at: i64,
+    expired: bool,
+}
+
+impl EntryInfo {
+    fn new(entry: cache::Model, now: i64) -> Self {
+        EntryInfo {
+            size: entry.payload.len() as u64,
+            hits: entry.hit_count,
+            created_at: entry.created_at,
+            last_hit_at: entry.last_hit_at,
+            expired: entry.created_at <= expired_before(&entry.translator, now),
+            translator: entry.translator,
+            source: entry.source,
+            target: entry.target,
+            query: entry.query,
+        }
+    }
+}
+
+/// Entries matching `filter`, newest first, `ttl` gives the seconds a translator's entries
+/// stay fresh, see [cache_time].
+pub(super) async fn select(
+    db: &DatabaseConnection,
+    filter: &CacheFilter,
+    now: i64,
+    ttl: impl Fn(&str) -> i64,
+) -> Result<Vec<cache::Model>, Error> {
+    Ok(cache::Entity::find()
+        .filter(filter.condition(now))
+        .order_by_desc(cache::Column::CreatedAt)
+        .order_by_desc(cache::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|entry| filter.matches(entry, now, &ttl))
+        .collect())
 }
 
-/// Clean all cache files in `APP_DIR`.
-pub fn clean() {
-    let mut s_size = 0u64;
+/// List the matching cache entries, one column per key field.
+pub async fn list(args: &ShowArgs) -> Result<(), Error> {
+    let db = connect().await?;
+    let now = now();
+    let mut entries = select(&db, &args.filter, now, cache_time)
+        .await?
+        .into_iter()
+        .map(|entry| EntryInfo::new(entry, now))
+        .collect::<Vec<_>>();
+    // stable, so ties stay newest first
+    match args.sort {
+        CacheSort::Age => {}
+        CacheSort::Size => entries.sort_by_key(|e| Reverse(e.size)),
+        CacheSort::Hits => entries.sort_by_key(|e| Reverse(e.hits)),
+    }
+    if args.reverse {
+        entries.reverse();
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let sum = entries.len();
+    let c_valid = entries.iter().filter(|e| !e.expired).count();
+    let s_size = entries.iter().map(|e| e.size).sum::<u64>();
+    let lines = entries
+        .into_iter()
+        .map(|entry| {
+            let (ff_size, ff_size_u) = fmt_size(entry.size);
+            [
+                String::from(if entry.expired { "✘" } else { "✔" }),
+                format!("{ff_size} {ff_size_u}"),
+                entry.hits.to_string(),
+                entry.translator,
+                format!("{}→{}", entry.source, entry.target),
+                entry.query,
+            ]
+        })
+        .collect::<Vec<_>>();
 
-    let (del, sum) = iter_dir(|de: DirEntry| {
-        let f_size = fs::metadata(de.path()).unwrap().len();
-        match fs::remove_file(de.path()) {
-            Ok(..) => {
-                s_size += f_size;
-                info!("removed: {:?}", de.file_name());
-                true
+    if !lines.is_empty() {
+        let header = ["", "SIZE", "HITS", "TRANSLATOR", "LANGS", "QUERY"].map(String::from);
+        let mut widths = [0usize; 6];
+        for line in std::iter::once(&header).chain(&lines) {
+            for (width, field) in widths.iter_mut().zip(line) {
+                *width = (*width).max(field.chars().count());
             }
-            Err(..) => false
         }
-    });
+        for [is_expired, size, hits, translator, langs, query] in
+            std::iter::once(header).chain(lines)
+        {
+            println!(
+                "{is_expired:<w0$} {size:>w1$} {hits:>w2$}  {translator:<w3$}  {langs:<w4$}  {query}",
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+                w4 = widths[4],
+            );
+        }
+    }
 
     let (s_size, s_size_u) = fmt_size(s_size);
-    println!("Removed {}/{} file(s), {} {}, file(s) in total.", del, sum, s_size, s_size_u)
-}
-
-/// Remove expired file
-pub fn purge() {
-    let mut s_size = 0u64;
-
-    let (del, sum) = iter_dir(|de: DirEntry| {
-        match unpack(de.file_name()) {
-            Ok(..) => false,
-            Err(..) => {
-                let f_size = fs::metadata(de.path()).unwrap().len();
-                match fs::remove_file(de.path()) {
-                    Err(..) => false,
-                    Ok(..) => {
-                        s_size += f_size;
-                        info!("removed: {:?}", de.file_name());
-                        true
-                    }
-                }
-            }
+    println!("{c_valid}/{sum} valid entries, {s_size} {s_size_u} in total.");
+
+    let limits = Limits::from_env();
+    let (t_sum, t_size) = stats(&db, Condition::all()).await?;
+    let mut usage = vec![];
+    if let Some(max_size) = limits.max_size {
+        let (t_size, t_size_u) = fmt_size(t_size);
+        let (max_size, max_size_u) = fmt_size(max_size);
+        usage.push(format!("{t_size} {t_size_u} of {max_size} {max_size_u}"));
+    }
+    if let Some(max_entries) = limits.max_entries {
+        usage.push(format!("{t_sum} of {max_entries} entries"));
+    }
+    if !usage.is_empty() {
+        println!("Limits: {}.", usage.join(", "));
+    }
+    Ok(())
+}
+
+/// Remove the matching cache entries, all of them without filters.
+pub async fn clean(filter: &CacheFilter) -> Result<(), Error> {
+    let db = connect().await?;
+    let ids = select(&db, filter, now(), cache_time)
+        .await?
+        .iter()
+        .map(|entry| entry.id.to_string())
+        .collect::<Vec<_>>();
+    // ids are integers, inlined to stay clear of SQLite's limit of bound variables
+    let matched = Expr::cust(format!("id IN ({})", ids.join(",")));
+    remove(&db, Condition::all().add(matched)).await
+}
+
+/// One entry, addressed like the query command addresses it.
+#[derive(Debug, Args)]
+pub struct EntryArgs {
+    #[command(flatten)]
+    pub translator: TranslatorArgs,
+
+    /// [strings] Words of the query
+    #[arg(num_args=1.., required = true)]
+    pub words: Vec<String>,
+}
+
+impl EntryArgs {
+    pub fn key(&self) -> Result<CacheKey, Error> {
+        Ok(CacheKey::new(
+            &self.words.join(" "),
+            &self.translator.source_lang,
+            &self.translator.target_lang,
+            &self.translator.cache_name()?,
+        ))
+    }
+}
+
+/// Print the cached payload of one entry and its age, without counting a hit.
+pub async fn get(args: &EntryArgs) -> Result<(), Error> {
+    let db = connect().await?;
+    let key = args.key()?;
+    let Some(entry) = find(&db, &key).await? else {
+        return Err(Error::CacheNotFound(key.to_string()));
+    };
+
+    let now = now();
+    let expired = if entry.created_at <= expired_before(&key.translator, now) {
+        ", expired"
+    } else {
+        ""
+    };
+    println!(
+        "{key}, cached {} ago{expired}, {} hits",
+        fmt_age(now - entry.created_at),
+        entry.hit_count
+    );
+    match serde_json::from_str::<serde_json::Value>(&entry.payload) {
+        Ok(payload) => println!("{}", serde_json::to_string_pretty(&payload)?),
+        Err(_) => println!("{}", entry.payload),
+    }
+    Ok(())
+}
+
+/// Remove one entry.
+pub async fn rm(args: &EntryArgs) -> Result<(), Error> {
+    let db = connect().await?;
+    let key = args.key()?;
+    let Some(entry) = find(&db, &key).await? else {
+        return Err(Error::CacheNotFound(key.to_string()));
+    };
+    cache::Entity::delete_by_id(entry.id).exec(&db).await?;
+    println!("Removed ({key}).");
+    Ok(())
+}
+
+/// Query the translator again and replace the entry, which is kept if that fails.
+pub async fn refresh(args: &EntryArgs) -> Result<(), Error> {
+    let key = args.key()?;
+    let Some(translator) = args.translator.online() else {
+        println!("Results of {} aren't cached.", args.translator.translator);
+        return Ok(());
+    };
+    let response = match translator
+        .translate(
+            &args.words.join(" "),
+            &args.translator.source_lang,
+            &args.translator.target_lang,
+        )
+        .await
+    {
+        Ok(response) => response,
+        Err(err) => {
+            eprintln!(
+                "{}",
+                format!("Refresh ({key}) failed: {err}").dye(Colors::Red)
+            );
+            return Err(err);
         }
-    });
+    };
+
+    let db = connect().await?;
+    let refreshed = find(&db, &key).await?.is_some();
+    put(&db, &key, serde_json::to_string(&response)?, now()).await?;
+    evict(&db, &Limits::from_env()).await?;
+    if refreshed {
+        println!("Refreshed ({key}).");
+    } else {
+        println!("Cached ({key}).");
+    }
+    Ok(())
+}
+
+/// Remove expired cache entries.
+pub async fn purge() -> Result<(), Error> {
+    let db = connect().await?;
+    let expired = expired(&db, now()).await?;
+    remove(&db, expired).await
+}
+
+async fn remove(db: &DatabaseConnection, condition: Condition) -> Result<(), Error> {
+    let (sum, _) = stats(db, Condition::all()).await?;
+    let (_, s_size) = stats(db, condition.clone()).await?;
+    let del = cache::Entity::delete_many()
+        .filter(condition)
+        .exec(db)
+        .await?
+        .rows_affected;
+    info!("removed {del} entries");
+    // give the space back to the file system
+    db.execute_unprepared("VACUUM").await?;
 
     let (s_size, s_size_u) = fmt_size(s_size);
-    println!("Removed {}/{} file(s), {} {}, file(s) in total.", del, sum, s_size, s_size_u)
+    println!(
+        "Removed {}/{} entries, {} {} in total.",
+        del, sum, s_size, s_size_u
+    );
+    Ok(())
+}
+
+/// Number and payload size of the entries matching `condition`.
+async fn stats(db: &DatabaseConnection, condition: Condition) -> Result<(u64, u64), Error> {
+    let (count, size) = cache::Entity::find()
+        .select_only()
+        .column_as(cache::Column::Id.count(), "count")
+        .column_as(
+            Expr::cust("COALESCE(SUM(LENGTH(CAST(payload AS BLOB))), 0)"),
+            "size",
+        )
+        .filter(condition)
+        .into_tuple::<(i64, i64)>()
+        .one(db)
+        .await?
+        .unwrap_or_default();
+    Ok((count as u64, size as u64))
 }
 
 mod test {
     #[cfg(test)]
-    use crate::utils::file::fmt_size;
+    use migration::{CacheMigrator, MigratorTrait};
+    #[cfg(test)]
+    use regex::Regex;
+    #[cfg(test)]
+    use sea_orm::Database;
+
+    #[cfg(test)]
+    use super::{fmt_age, parse_age, select, CacheFilter};
+    #[cfg(test)]
+    use crate::{
+        cache::{put, CacheKey},
+        translators::Lang,
+        utils::file::fmt_size,
+    };
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90"), Ok(90));
+        assert_eq!(parse_age("30m"), Ok(1800));
+        assert_eq!(parse_age("2w"), Ok(86400 * 14));
+        assert!(parse_age("2 days").is_err());
+        assert!(parse_age("99999999999999w").is_err());
+        assert_eq!(fmt_age(parse_age("2w").unwrap()), "14d");
+        assert_eq!(fmt_age(3599), "59m");
+    }
+
+    #[tokio::test]
+    async fn test_select() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        CacheMigrator::up(&db, None).await.unwrap();
+        for (query, source, translator, created_at) in [
+            ("hello", Lang::En, "google", 100),
+            ("hello world", Lang::En, "youdao", 200),
+            ("你好", Lang::Zh, "http.my-api", 300),
+        ] {
+            let key = CacheKey::new(query, &source, &Lang::Zh, translator);
+            put(&db, &key, "\"\"".to_string(), created_at)
+                .await
+                .unwrap();
+        }
+        let queries = |filter: CacheFilter| {
+            let db = &db;
+            async move {
+                select(db, &filter, 400, |_| 300)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|m| m.query)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            queries(CacheFilter::default()).await,
+            vec!["你好", "hello world", "hello"]
+        );
+        let http = CacheFilter {
+            translator: vec!["http".to_string(), "google".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(queries(http).await, vec!["你好", "hello"]);
+        let english = CacheFilter {
+            source_lang: Some(Lang::En),
+            query: Some("WORLD".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(queries(english).await, vec!["hello world"]);
+        let single = CacheFilter {
+            regex: Some(Regex::new(r"^\w+$").unwrap()),
+            older_than: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(queries(single).await, vec!["hello"]);
+        // 300 seconds of TTL
+        let expired = CacheFilter {
+            expired: true,
+            ..Default::default()
+        };
+        assert_eq!(queries(expired).await, vec!["hello"]);
+        let valid = CacheFilter {
+            valid: true,
+            newer_than: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(queries(valid).await, vec!["你好"]);
+    }
 
     #[test]
     fn test_format() {
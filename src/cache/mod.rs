@@ -1,59 +1,387 @@
-use log::{error, warn};
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+//! Results of online translators cached in SQLite, `~/.cache/runslate/cache.db` by default,
+//! one row per `(translator, source, target, normalized query)`, looked up by its hash so
+//! queries of any length and character are told apart.
+//!
+//! Files of the former one-file-per-query cache are moved into the database when it's opened.
 
-use crate::{
-    errors::Error,
-    utils::file::{remove_file, write_string},
+use std::fmt::Display;
+
+use log::{error, info, warn};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    QuerySelect,
 };
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{errors::Error, translators::Lang};
 
-use self::util::{pack, unpack};
+use self::{
+    entities::cache,
+    util::{connect, expired_before, now, Limits},
+};
 
+pub mod archive;
 pub mod cmd;
-pub(self) mod util;
+pub mod entities;
+mod util;
+
+/// What a cached result is looked up by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheKey {
+    pub translator: String,
+    pub source: String,
+    pub target: String,
+    pub query: String,
+}
+
+impl CacheKey {
+    pub fn new(query: &str, source: &Lang, target: &Lang, translator: &str) -> Self {
+        CacheKey {
+            translator: translator.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            query: normalize(query),
+        }
+    }
+
+    /// Hex SHA-256 of the fields, JSON-encoded so no two keys run together.
+    pub fn hash(&self) -> String {
+        let fields = [&self.translator, &self.source, &self.target, &self.query];
+        sha256::digest(serde_json::to_string(&fields).unwrap_or_default())
+    }
+}
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}→{} {}",
+            self.translator, self.source, self.target, self.query
+        )
+    }
+}
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct CacheRecord {
-    data: String,
-    created_at: u64,
+/// A cached value, `stale` once the TTL of its translator is over. Stale values are kept to
+/// be shown when the translator can't be reached, until a fresh one replaces them.
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    pub stale: bool,
 }
 
-pub(crate) fn set<T>(key: &str, value: T) -> bool
+/// Trimmed, with runs of whitespace collapsed into a space.
+pub fn normalize(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub(crate) async fn set<T>(key: &CacheKey, value: T) -> bool
 where
     T: Sized + Serialize,
 {
-    let Ok(record) = pack(value) else {
-        return false;
+    let payload = match serde_json::to_string(&value) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("Serialize value of ({}) failed: {:?}", key, e);
+            return false;
+        }
     };
 
-    match serde_json::to_string(&record) {
-        Ok(content) => write_string(key, content),
+    let result = match connect().await {
+        Ok(db) => match put(&db, key, payload, now()).await {
+            Ok(()) => evict(&db, &Limits::from_env()).await.map(|_| ()),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => true,
         Err(e) => {
-            error!("Serialize key ({}) failed: {:?}", key, e);
+            error!("Save cache of ({}) failed: {:?}", key, e);
             false
         }
     }
 }
 
-pub(crate) fn get<'a, T>(key: String) -> Result<T, Error>
+pub(crate) async fn get<T>(key: &CacheKey) -> Result<Cached<T>, Error>
 where
     T: DeserializeOwned,
 {
-    let record = match unpack::<&str>(&key) {
-        Ok(record) => record,
-        Err(_) => {
-            let msg = format!("Cache expired or not found");
-            warn!("{msg}");
-            remove_file(&key);
-            return Err(Error::CacheNotFound("".to_string()));
-        }
-    };
+    let db = connect().await?;
+    let Cached { value, stale } = fetch(&db, key, now()).await?;
 
-    match serde_json::from_str::<T>(&record.data) {
-        Ok(value) => return Ok(value),
+    match serde_json::from_str::<T>(&value) {
+        Ok(value) => Ok(Cached { value, stale }),
         Err(e) => {
             let msg = format!("Deserialize value failed: {e}");
             error!("{msg}");
-            return Err(Error::DeserializeFailed(msg));
+            Err(Error::DeserializeFailed(msg))
         }
     }
 }
+
+/// Insert the entry of `key`, replacing an older one.
+pub async fn put(
+    db: &DatabaseConnection,
+    key: &CacheKey,
+    payload: String,
+    now: i64,
+) -> Result<(), Error> {
+    cache::Entity::insert(cache::ActiveModel {
+        translator: Set(key.translator.clone()),
+        source: Set(key.source.clone()),
+        target: Set(key.target.clone()),
+        query: Set(key.query.clone()),
+        payload: Set(payload),
+        created_at: Set(now),
+        last_hit_at: Set(now),
+        hit_count: Set(0),
+        key_hash: Set(key.hash()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(cache::Column::KeyHash)
+            .update_columns([
+                cache::Column::Payload,
+                cache::Column::CreatedAt,
+                cache::Column::LastHitAt,
+                cache::Column::HitCount,
+            ])
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+/// Entry of `key`, without counting a hit.
+pub async fn find(db: &DatabaseConnection, key: &CacheKey) -> Result<Option<cache::Model>, Error> {
+    Ok(cache::Entity::find()
+        .filter(cache::Column::KeyHash.eq(key.hash()))
+        .one(db)
+        .await?)
+}
+
+/// Payload of the entry of `key`, counting the hit.
+pub async fn fetch(
+    db: &DatabaseConnection,
+    key: &CacheKey,
+    now: i64,
+) -> Result<Cached<String>, Error> {
+    let Some(entry) = find(db, key).await? else {
+        return Err(Error::CacheNotFound(key.to_string()));
+    };
+
+    let stale = entry.created_at <= expired_before(&key.translator, now);
+    if stale {
+        warn!("Cache of ({}) is stale", key);
+    }
+
+    let payload = entry.payload.clone();
+    let hit_count = entry.hit_count + 1;
+    let mut entry = entry.into_active_model();
+    entry.last_hit_at = Set(now);
+    entry.hit_count = Set(hit_count);
+    entry.update(db).await?;
+    Ok(Cached {
+        value: payload,
+        stale,
+    })
+}
+
+/// Remove least recently used entries until the cache is within `limits`, returns the number
+/// of entries removed. The most recent entry is kept even if it alone is over the size.
+pub async fn evict(db: &DatabaseConnection, limits: &Limits) -> Result<u64, Error> {
+    if *limits == Limits::default() {
+        return Ok(0);
+    }
+    // most saves leave the cache within the limits, find out without reading every entry
+    let (count, size) = cache::Entity::find()
+        .select_only()
+        .column_as(Expr::cust("COUNT(*)"), "count")
+        .column_as(
+            Expr::cust("COALESCE(SUM(LENGTH(CAST(payload AS BLOB))), 0)"),
+            "size",
+        )
+        .into_tuple::<(i64, i64)>()
+        .one(db)
+        .await?
+        .unwrap_or_default();
+    let over_entries = limits.max_entries.is_some_and(|max| count as u64 > max);
+    let over_size = limits.max_size.is_some_and(|max| size as u64 > max);
+    if !over_entries && !over_size {
+        return Ok(0);
+    }
+
+    let entries = cache::Entity::find()
+        .select_only()
+        .column(cache::Column::Id)
+        .column_as(Expr::cust("LENGTH(CAST(payload AS BLOB))"), "size")
+        .order_by_desc(cache::Column::LastHitAt)
+        .order_by_desc(cache::Column::Id)
+        .into_tuple::<(i32, i64)>()
+        .all(db)
+        .await?;
+
+    let (mut count, mut size) = (0u64, 0u64);
+    let mut evicted = vec![];
+    for (id, f_size) in entries {
+        count += 1;
+        size += f_size as u64;
+        let over_entries = limits.max_entries.is_some_and(|max| count > max);
+        let over_size = limits.max_size.is_some_and(|max| size > max);
+        if count > 1 && (over_entries || over_size) {
+            evicted.push(id);
+        }
+    }
+    if evicted.is_empty() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    // stay below SQLite's limit of bound variables
+    for ids in evicted.chunks(512) {
+        removed += cache::Entity::delete_many()
+            .filter(cache::Column::Id.is_in(ids.iter().copied()))
+            .exec(db)
+            .await?
+            .rows_affected;
+    }
+    info!("Evicted {removed} least recently used cache entries.");
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{CacheMigrator, MigratorTrait};
+    use sea_orm::{Database, EntityTrait, PaginatorTrait, QueryFilter};
+
+    use super::{
+        entities::cache,
+        evict, fetch, normalize, put,
+        util::{expired, import_files, Limits},
+        CacheKey,
+    };
+    use crate::{errors::Error, translators::Lang};
+
+    #[tokio::test]
+    async fn test_cache() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        CacheMigrator::up(&db, None).await.unwrap();
+        assert_eq!(normalize("  hello \t world "), "hello world");
+        let hash = |query: &str, source: &Lang, target: &Lang| {
+            CacheKey::new(query, source, target, "google").hash()
+        };
+        assert_ne!(
+            hash("a/b", &Lang::En, &Lang::Zh),
+            hash("a-b", &Lang::En, &Lang::Zh)
+        );
+        assert_ne!(
+            hash("a", &Lang::En, &Lang::Zh),
+            hash("a", &Lang::Zh, &Lang::En)
+        );
+        assert_eq!(hash(&"long ".repeat(1000), &Lang::En, &Lang::Zh).len(), 64);
+
+        let key = CacheKey::new(" hello  world", &Lang::Auto, &Lang::Zh, "youdao");
+        assert!(matches!(
+            fetch(&db, &key, 100).await,
+            Err(Error::CacheNotFound(_))
+        ));
+        put(&db, &key, "\"你好\"".to_string(), 100).await.unwrap();
+        put(&db, &key, "\"你好世界\"".to_string(), 100)
+            .await
+            .unwrap();
+        let same = CacheKey::new("hello world ", &Lang::Auto, &Lang::Zh, "youdao");
+        let cached = fetch(&db, &same, 110).await.unwrap();
+        assert_eq!(
+            (cached.value.as_str(), cached.stale),
+            ("\"你好世界\"", false)
+        );
+        let entry = cache::Entity::find().one(&db).await.unwrap().unwrap();
+        assert_eq!((entry.hit_count, entry.last_hit_at), (1, 110));
+
+        // expired entries are kept for when the translator is unreachable
+        let later = 100 + 86400 * 365;
+        assert!(fetch(&db, &key, later).await.unwrap().stale);
+        let expired = |now| {
+            let db = &db;
+            async move {
+                cache::Entity::find()
+                    .filter(expired(db, now).await.unwrap())
+                    .count(db)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!((expired(110).await, expired(later).await), (0, 1));
+        cache::Entity::delete_many().exec(&db).await.unwrap();
+        assert_eq!(expired(later).await, 0);
+
+        // files of the file cache are moved in, other files are left
+        let dir = std::env::temp_dir().join(format!("runslate_test_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("Zh-google-En_hello-world"),
+            r#"{"data": "\"你好世界\"", "created_at": 100}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+        assert_eq!(import_files(&db, &dir).await.unwrap(), 1);
+        assert!(!dir.join("Zh-google-En_hello-world").exists());
+        assert!(dir.join("notes.txt").exists());
+        let key = CacheKey::new("hello world", &Lang::En, &Lang::Zh, "google");
+        assert_eq!(fetch(&db, &key, 110).await.unwrap().value, "\"你好世界\"");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evict() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        CacheMigrator::up(&db, None).await.unwrap();
+        let key = |query| CacheKey::new(query, &Lang::En, &Lang::Zh, "google");
+        for (i, query) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            put(&db, &key(query), "0123456789".to_string(), i as i64)
+                .await
+                .unwrap();
+        }
+        // reading `a` makes `b` the least recently used
+        fetch(&db, &key("a"), 10).await.unwrap();
+        let left = || {
+            let db = &db;
+            async move {
+                let mut queries = cache::Entity::find()
+                    .all(db)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|m| m.query)
+                    .collect::<Vec<_>>();
+                queries.sort();
+                queries
+            }
+        };
+
+        assert_eq!(evict(&db, &Limits::default()).await.unwrap(), 0);
+        let limits = Limits {
+            max_entries: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(evict(&db, &limits).await.unwrap(), 1);
+        assert_eq!(left().await, vec!["a", "c", "d"]);
+        let limits = Limits {
+            max_size: Some(25),
+            ..Default::default()
+        };
+        assert_eq!(evict(&db, &limits).await.unwrap(), 1);
+        assert_eq!(left().await, vec!["a", "d"]);
+        // the newest entry stays whatever its size
+        let limits = Limits {
+            max_size: Some(1),
+            ..Default::default()
+        };
+        evict(&db, &limits).await.unwrap();
+        assert_eq!(left().await, vec!["a"]);
+    }
+}
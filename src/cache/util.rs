@@ -1,63 +1,318 @@
 use std::{
-    path::Path,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use log::{debug, info};
-use serde::Serialize;
+use log::{debug, error, info};
+use migration::{CacheMigrator, MigratorTrait};
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, Database,
+    DatabaseConnection, DbBackend, EntityTrait, QuerySelect, Statement,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     errors::Error,
-    utils::{env_loader, file::read_string},
+    utils::{env_loader, file::app_dir},
 };
 
-use super::CacheRecord;
+use super::{entities::cache, normalize, CacheKey};
+
+/// Cache database in the app cache directory.
+const CACHE_FILE: &str = "cache.db";
+
+/// `user_version` of the cache database once the former file cache is moved in.
+const FILES_IMPORTED: i64 = 1;
+
+/// Whether this process has already migrated the cache database.
+static MIGRATED: AtomicBool = AtomicBool::new(false);
+
+/// An entry of the former file cache, one JSON file per query.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CacheRecord {
+    pub data: String,
+    pub created_at: u64,
+}
 
-/// Serialize value and pack it in [CacheRecord].
-pub fn pack<T>(value: T) -> Result<CacheRecord, Error>
-where
-    T: Sized + Serialize,
-{
-    let data = serde_json::to_string::<T>(&value)?;
-    let created_at = SystemTime::now()
+/// Seconds since the unix epoch.
+pub fn now() -> i64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_secs();
+        .as_secs() as i64
+}
+
+/// Entries of `translator` created at or before this time are stale.
+pub fn expired_before(translator: &str, now: i64) -> i64 {
+    let cache_ttl = cache_time(translator);
+    debug!("Cache time of {}: {}", translator, cache_ttl);
+    now - cache_ttl
+}
+
+/// Seconds entries of `translator` stay fresh: `RUNSLATE_CACHE_TIME_<TRANSLATOR>`, then
+/// `RUNSLATE_CACHE_TIME_HTTP` for user-defined backends, then `RUNSLATE_CACHE_TIME`.
+pub fn cache_time(translator: &str) -> i64 {
+    let default = env_loader::load_or_default("RUNSLATE_CACHE_TIME", "300");
+    let mut vars = vec![ttl_var(translator)];
+    if let Some((base, _)) = translator.split_once('.') {
+        vars.push(ttl_var(base));
+    }
+    vars.iter()
+        .find_map(|var| env_loader::load(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or(default)
+        .trim()
+        .parse::<i64>()
+        .unwrap_or(300)
+}
 
-    Ok(CacheRecord { data, created_at })
+/// `http.my-api` is configured by `RUNSLATE_CACHE_TIME_HTTP_MY_API`.
+fn ttl_var(translator: &str) -> String {
+    let name = translator
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("RUNSLATE_CACHE_TIME_{name}")
 }
 
-/// Read file and deserialize file content to [CacheRecord].
-/// If cache expired, an [Error::CacheExpired] will be returned.
-pub fn unpack<P: AsRef<Path>>(f_name: P) -> Result<CacheRecord, Error> {
-    match read_string(&f_name) {
-        Ok(value) => match serde_json::from_str::<CacheRecord>(&value) {
-            Ok(record) => {
-                info!("Deserialize file content successfully.");
+/// Entries past the TTL of their translator.
+pub async fn expired(db: &DatabaseConnection, now: i64) -> Result<Condition, Error> {
+    let translators = cache::Entity::find()
+        .select_only()
+        .column(cache::Column::Translator)
+        .distinct()
+        .into_tuple::<String>()
+        .all(db)
+        .await?;
+    // nothing matches an empty `any`
+    Ok(translators
+        .into_iter()
+        .fold(Condition::any(), |condition, translator| {
+            let before = expired_before(&translator, now);
+            condition.add(
+                Condition::all()
+                    .add(cache::Column::Translator.eq(translator))
+                    .add(cache::Column::CreatedAt.lte(before)),
+            )
+        }))
+}
 
-                let cache_ttl = cache_time();
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                debug!("Cache time: {}", cache_ttl);
+/// Bounds of the cache, unlimited unless set.
+#[derive(Debug, Default, PartialEq)]
+pub struct Limits {
+    /// Total payload bytes, `RUNSLATE_CACHE_MAX_SIZE`, e.g. `20MB`
+    pub max_size: Option<u64>,
+    /// `RUNSLATE_CACHE_MAX_ENTRIES`
+    pub max_entries: Option<u64>,
+}
 
-                if record.created_at + cache_ttl > now {
-                    return Ok(record);
-                }
+impl Limits {
+    pub fn from_env() -> Self {
+        let load = |key| env_loader::load(key).ok().filter(|v| !v.trim().is_empty());
+        Limits {
+            max_size: load("RUNSLATE_CACHE_MAX_SIZE").and_then(|v| parse_size(&v)),
+            max_entries: load("RUNSLATE_CACHE_MAX_ENTRIES").and_then(|v| v.trim().parse().ok()),
+        }
+    }
+}
 
-                Err(Error::CacheExpired(
-                    f_name.as_ref().to_string_lossy().to_string(),
-                ))
-            }
-            Err(e) => Err(Error::DeserializeFailed(e.to_string())),
-        },
-        Err(e) => Err(e),
+/// Bytes of `1024`, `512K`, `20MB` or `1 GB`, units are powers of 1024 like `fmt_size`'s.
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_ascii_uppercase();
+    let unit_at = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_at);
+    let shift = match unit.trim().trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
+    };
+    let number = number.parse::<f64>().ok()?;
+    Some((number * (1u64 << shift) as f64) as u64)
+}
+
+/// `RUNSLATE_CACHE_DB`, or `cache.db` in the app cache directory.
+pub fn database_path() -> PathBuf {
+    match env_loader::load("RUNSLATE_CACHE_DB") {
+        Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+        _ => app_dir().join(CACHE_FILE),
     }
 }
 
-fn cache_time() -> u64 {
-    env_loader::load_or_default("RUNSLATE_CACHE_TIME", "300")
-        .parse::<u64>()
-        .unwrap_or(300)
+/// Open the cache database, migrating it on the first call. The files of the former file
+/// cache are moved into it until that succeeds once.
+pub async fn connect() -> Result<DatabaseConnection, Error> {
+    let path = database_path();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", path.display())).await?;
+    if !MIGRATED.load(Ordering::Relaxed) {
+        CacheMigrator::up(&db, None).await?;
+        import_legacy(&db).await?;
+        MIGRATED.store(true, Ordering::Relaxed);
+    }
+    Ok(db)
+}
+
+/// Move the former file cache in unless it's done, a failed move is retried next time.
+async fn import_legacy(db: &DatabaseConnection) -> Result<(), Error> {
+    let version = db
+        .query_one_raw(Statement::from_string(
+            DbBackend::Sqlite,
+            "PRAGMA user_version",
+        ))
+        .await?
+        .map(|row| row.try_get_by_index::<i64>(0))
+        .transpose()?
+        .unwrap_or(0);
+    if version >= FILES_IMPORTED {
+        return Ok(());
+    }
+    let dir = app_dir();
+    match import_files(db, &dir).await {
+        Ok(_) => {
+            db.execute_unprepared(&format!("PRAGMA user_version = {FILES_IMPORTED}"))
+                .await?;
+        }
+        Err(e) => error!(
+            "Move cache files in {} into the cache database failed, retry next time: {:?}",
+            dir.display(),
+            e
+        ),
+    }
+    Ok(())
+}
+
+/// Move cache files in `dir` into the database, returns the number of files moved.
+/// Files which aren't cache files are left alone.
+pub async fn import_files(db: &DatabaseConnection, dir: &Path) -> Result<u64, Error> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some((target, translator, source, query)) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_file_name)
+        else {
+            continue;
+        };
+        let Some(record) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheRecord>(&content).ok())
+        else {
+            continue;
+        };
+
+        // the database is newer than any file left behind
+        let key = CacheKey {
+            translator,
+            source,
+            target,
+            query: normalize(&query),
+        };
+        cache::Entity::insert(cache::ActiveModel {
+            key_hash: Set(key.hash()),
+            translator: Set(key.translator),
+            source: Set(key.source),
+            target: Set(key.target),
+            query: Set(key.query),
+            payload: Set(record.data),
+            created_at: Set(record.created_at as i64),
+            last_hit_at: Set(record.created_at as i64),
+            hit_count: Set(0),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(cache::Column::KeyHash)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+        fs::remove_file(&path)?;
+        count += 1;
+    }
+    if count > 0 {
+        info!("Moved {count} cache file(s) into the cache database.");
+    }
+    Ok(count)
+}
+
+/// `(target, translator, source, query)` of a file name like `Zh-youdao-Auto_hello-world`,
+/// which the file cache wrote target first. The file name had `-` for spaces and the
+/// characters it couldn't hold, they are read back as spaces.
+fn parse_file_name(name: &str) -> Option<(String, String, String, String)> {
+    let (prefix, query) = name.split_once('_')?;
+    let (target, rest) = prefix.split_once('-')?;
+    let (translator, source) = rest.rsplit_once('-')?;
+    let query = query.replace('-', " ");
+    if [target, translator, source, query.trim()]
+        .iter()
+        .any(|s| s.is_empty())
+    {
+        return None;
+    }
+    Some((
+        target.to_string(),
+        translator.to_string(),
+        source.to_string(),
+        query,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_file_name, parse_size, ttl_var};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("512k"), Some(512 << 10));
+        assert_eq!(parse_size(" 1.5 MB"), Some(3 << 19));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("2 apples"), None);
+    }
+
+    #[test]
+    fn test_ttl_var() {
+        assert_eq!(
+            ttl_var("dictionaryapi"),
+            "RUNSLATE_CACHE_TIME_DICTIONARYAPI"
+        );
+        assert_eq!(ttl_var("http.my-api"), "RUNSLATE_CACHE_TIME_HTTP_MY_API");
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(
+            parse_file_name("Zh-http.my-api-Auto_hello-world"),
+            Some((
+                "Zh".into(),
+                "http.my-api".into(),
+                "Auto".into(),
+                "hello world".into()
+            ))
+        );
+        assert_eq!(parse_file_name("cache.db"), None);
+        assert_eq!(parse_file_name("Auto-youdao_hello"), None);
+        assert_eq!(parse_file_name("Zh-youdao-En_-"), None);
+    }
 }
@@ -1,4 +1,3 @@
-
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Environment variable does not exist: {0}")]
@@ -9,19 +8,19 @@ pub enum Error {
 
     #[error("External crate internal error: {0}")]
     OuterCrateInternalError(String),
-    
+
     #[error("Cache expired: {0}")]
     CacheExpired(String),
-    
+
     #[error("Cache not found: {0}")]
     CacheNotFound(String),
-    
+
     #[error("Serialization failed: {0}")]
     SerializeError(#[from] serde_json::Error),
-    
+
     #[error("Deserialization failed: {0}")]
     DeserializeFailed(String),
-    
+
     #[error("Read file error: {0}")]
     ReadFileError(String),
 
@@ -33,4 +32,31 @@ pub enum Error {
 
     #[error("Network error during translation: {0}")]
     TranslateNetworkError(#[from] reqwest::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Dict server error: {0}")]
+    DictServerError(String),
+
+    #[error("Config error: {0}")]
+    ConfigError(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+
+    #[error("Database error: {0}")]
+    Database(String),
+
+    #[error("FST error: {0}")]
+    FstError(#[from] fst::Error),
+}
+
+impl From<sea_orm::DbErr> for Error {
+    fn from(e: sea_orm::DbErr) -> Self {
+        Error::Database(e.to_string())
+    }
 }
@@ -1,12 +1,16 @@
 use std::env;
 
 use clap::Parser;
-use log::{debug, info};
+use log::{debug, error, info};
 use runslate::{
-    args::{CacheCommands, Cli, Commands},
+    args::{CacheCommands, Cli, Commands, DictCommands, EcdictCommands},
     cache,
-    translators::translate,
-    utils::env_loader,
+    errors::Error,
+    translators::{dict, ecdict, translate},
+    utils::{
+        dyer::{Colors, Dye},
+        env_loader,
+    },
 };
 
 #[tokio::main]
@@ -24,6 +28,10 @@ async fn main() {
         "RUNSLATE_TARGET_LANG",
         "RUNSLATE_SHOW_MORE",
         "RUNSLATE_VERBOSE",
+        "RUNSLATE_DICT_DATABASE",
+        "RUNSLATE_DICT_STRATEGY",
+        "RUNSLATE_HTTP_BACKEND",
+        "RUNSLATE_WOTD_WINDOW",
     ]);
 
     // parse arguments
@@ -42,10 +50,85 @@ async fn main() {
                 load_result.unwrap_or("doesn't exist".to_string())
             );
 
-            match args.commands {
-                CacheCommands::Clean => cache::cmd::clean(),
-                CacheCommands::Show => cache::cmd::list(),
-                CacheCommands::Purge => cache::cmd::purge(),
+            let result = match args.commands {
+                CacheCommands::Clean(filter) => cache::cmd::clean(&filter).await,
+                CacheCommands::Show(args) => cache::cmd::list(&args).await,
+                CacheCommands::Purge => cache::cmd::purge().await,
+                CacheCommands::Get(args) => cache::cmd::get(&args).await,
+                CacheCommands::Rm(args) => cache::cmd::rm(&args).await,
+                CacheCommands::Refresh(args) => cache::cmd::refresh(&args).await,
+                CacheCommands::Export { file, zstd } => cache::archive::export(&file, zstd).await,
+                CacheCommands::Import { file, on_conflict } => {
+                    cache::archive::import(&file, &on_conflict).await
+                }
+            };
+            match result {
+                Err(Error::CacheNotFound(key)) => {
+                    eprintln!("{}", format!("Not cached: ({key})").dye(Colors::Red))
+                }
+                Err(Error::Database(msg)) => eprintln!("{}", msg.dye(Colors::Red)),
+                Err(err) => error!("{:#?}", err),
+                Ok(_) => (),
+            }
+        }
+        Commands::Dict(args) => {
+            // set verbose
+            if !args.verbose {
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            // log args
+            debug!("{:#?}", args);
+            info!(
+                "Load file .env: {}",
+                load_result.unwrap_or("doesn't exist".to_string())
+            );
+
+            let result = match args.commands {
+                DictCommands::Databases => dict::databases().await,
+                DictCommands::Strategies => dict::strategies().await,
+                DictCommands::Match {
+                    database,
+                    strategy,
+                    words,
+                } => dict::suggest(&words.join(" "), &database, &strategy).await,
+            };
+            if let Err(err) = result {
+                error!("{:#?}", err);
+            }
+        }
+        Commands::Ecdict(args) => {
+            // set verbose
+            if !args.verbose {
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            // log args
+            debug!("{:#?}", args);
+            info!(
+                "Load file .env: {}",
+                load_result.unwrap_or("doesn't exist".to_string())
+            );
+
+            let result = match &args.commands {
+                EcdictCommands::Import {
+                    path,
+                    dataset,
+                    force,
+                    index,
+                } => ecdict::import::import(path, *dataset, *force, *index).await,
+                EcdictCommands::Index => ecdict::fst_index::index().await,
+                EcdictCommands::Complete {
+                    prefix,
+                    limit,
+                    fuzzy,
+                } => ecdict::fst_index::complete(prefix, *limit, *fuzzy),
+                EcdictCommands::Search(search) => ecdict::search::search(search).await,
+                EcdictCommands::Random(random) => ecdict::random::random(random).await,
+            };
+            match result {
+                Err(Error::Database(msg)) => eprintln!("{}", msg.dye(Colors::Red)),
+                Err(Error::InvalidArgument(msg)) => eprintln!("{}", msg.dye(Colors::Red)),
+                Err(err) => error!("{:#?}", err),
+                Ok(_) => (),
             }
         }
         Commands::Query(args) => {
@@ -62,5 +145,23 @@ async fn main() {
 
             translate(args).await;
         }
+        Commands::Wotd(args) => {
+            // set verbose
+            if !args.verbose {
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            // log args
+            debug!("{:#?}", args);
+            info!(
+                "Load file .env: {}",
+                load_result.unwrap_or("doesn't exist".to_string())
+            );
+
+            match ecdict::random::wotd(&args).await {
+                Err(Error::Database(msg)) => eprintln!("{}", msg.dye(Colors::Red)),
+                Err(err) => error!("{:#?}", err),
+                Ok(_) => (),
+            }
+        }
     }
 }
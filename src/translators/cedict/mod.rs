@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+
+use log::{debug, trace};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
+use serde_json::{json, Value};
+
+use crate::{
+    errors::Error,
+    translators::{
+        ecdict::{check_imported, connect_db_for},
+        Lang, Translator,
+    },
+    utils::dyer::{Colors, Dye},
+};
+
+pub mod entities;
+
+/// Name and import command of the dictionary, for the hints of errors.
+const NAME: &str = "CC-CEDICT";
+const IMPORT_COMMAND: &str = "cargo run --bin cedict -- cedict_ts.u8";
+
+/// Longest headword tried by segmentation, in characters.
+const MAX_WORD_LEN: usize = 12;
+
+/// Offline Chinese-English dictionary, imported from CC-CEDICT by `bin/cedict.rs`.
+pub struct Cedict;
+
+#[async_trait::async_trait]
+impl Translator for Cedict {
+    async fn translate(&self, words: &str, _source: &Lang, _target: &Lang) -> Result<Value, Error> {
+        trace!("Cedict: Start to query database.");
+
+        let db = connect_db_for(NAME, IMPORT_COMMAND).await?;
+        check_imported::<entities::cedict::Entity>(&db, NAME, IMPORT_COMMAND).await?;
+        let query: String = words.chars().filter(|c| !c.is_whitespace()).collect();
+        let candidates = substrings(&query);
+        let mut rows = vec![];
+        // stay below SQLite's limit of bound variables, each candidate is bound twice
+        for chunk in candidates.chunks(256) {
+            let found = entities::cedict::Entity::find()
+                .filter(
+                    Condition::any()
+                        .add(entities::cedict::Column::Simplified.is_in(chunk.iter().cloned()))
+                        .add(entities::cedict::Column::Traditional.is_in(chunk.iter().cloned())),
+                )
+                .order_by_asc(entities::cedict::Column::Id)
+                .all(&db)
+                .await?;
+            rows.extend(found);
+        }
+        // an entry matches candidates of several chunks by either script
+        rows.sort_by_key(|row| row.id);
+        rows.dedup_by_key(|row| row.id);
+        debug!("{} candidate entries found.", rows.len());
+
+        // headword -> entries, both scripts indexed
+        let mut dict: HashMap<String, Vec<Value>> = HashMap::new();
+        for row in rows {
+            let entry = json!({
+                "simplified": row.simplified,
+                "traditional": row.traditional,
+                "pinyin": tone_marks(&row.pinyin),
+                "definitions": serde_json::from_str::<Value>(&row.definitions)?,
+            });
+            let mut keys = vec![row.simplified.clone()];
+            if row.traditional != row.simplified {
+                keys.push(row.traditional.clone());
+            }
+            for key in keys {
+                dict.entry(key).or_default().push(entry.clone());
+            }
+        }
+
+        let segments = segment(&query, |w| dict.contains_key(w))
+            .into_iter()
+            .map(|text| {
+                let entries = dict.get(&text).cloned().unwrap_or_default();
+                json!({"text": text, "entries": entries})
+            })
+            .collect::<Vec<Value>>();
+
+        Ok(json!({
+            "word": query,
+            "segments": segments,
+        }))
+    }
+
+    fn show(&self, response: &Value, more: bool) {
+        trace!("Cedict: parsing response data.");
+
+        let Some(Value::Array(segments)) = response.get("segments") else {
+            return;
+        };
+        for segment in segments {
+            let Some(Value::Array(entries)) = segment.get("entries") else {
+                continue;
+            };
+            for entry in entries {
+                let simplified = entry
+                    .get("simplified")
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                let traditional = entry
+                    .get("traditional")
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                let pinyin = entry.get("pinyin").and_then(Value::as_str).unwrap_or("");
+                let headword = if simplified == traditional {
+                    simplified.to_string()
+                } else {
+                    format!("{simplified} ({traditional})")
+                };
+                println!(
+                    "{} {}",
+                    headword.dye(Colors::BrightWhite),
+                    format!("[{pinyin}]").dye(Colors::BrightYellow)
+                );
+
+                let definitions: Vec<&str> = match entry.get("definitions") {
+                    Some(Value::Array(defs)) => defs.iter().filter_map(Value::as_str).collect(),
+                    _ => vec![],
+                };
+                if more {
+                    for (idx, definition) in definitions.iter().enumerate() {
+                        println!("{}. {}", idx + 1, definition.dye(Colors::Cyan));
+                    }
+                } else {
+                    println!("{}", definitions.join("; ").dye(Colors::Cyan));
+                }
+            }
+        }
+
+        trace!("Response parsed.");
+    }
+}
+
+/// An entry of the CC-CEDICT text file.
+#[derive(Debug, PartialEq)]
+pub struct CedictLine {
+    pub traditional: String,
+    pub simplified: String,
+    pub pinyin: String,
+    pub definitions: Vec<String>,
+}
+
+/// Parse `Traditional Simplified [pin1 yin1] /gloss 1/gloss 2/`, `None` for comments.
+pub fn parse_line(line: &str) -> Option<CedictLine> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let (traditional, rest) = line.split_once(' ')?;
+    let (simplified, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix('[')?;
+    let (pinyin, rest) = rest.split_once(']')?;
+    let definitions = rest
+        .trim()
+        .trim_matches('/')
+        .split('/')
+        .filter(|d| !d.is_empty())
+        .map(String::from)
+        .collect();
+
+    Some(CedictLine {
+        traditional: traditional.to_string(),
+        simplified: simplified.to_string(),
+        pinyin: pinyin.to_string(),
+        definitions,
+    })
+}
+
+/// Convert numbered pinyin to tone marks, e.g. `nu:3 er2` -> `nǚ ér`.
+pub fn tone_marks(pinyin: &str) -> String {
+    pinyin
+        .split(' ')
+        .map(syllable_tone_mark)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn syllable_tone_mark(syllable: &str) -> String {
+    let syllable = umlaut(syllable);
+    let Some(tone) = syllable
+        .chars()
+        .last()
+        .and_then(|c| c.to_digit(10))
+        .filter(|t| (1..=5).contains(t))
+    else {
+        return syllable;
+    };
+    let chars: Vec<char> = syllable[..syllable.len() - 1].chars().collect();
+    if tone == 5 {
+        return chars.into_iter().collect();
+    }
+
+    // `a`/`e` take the mark, `o` in `ou`, otherwise the last vowel
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let idx = lower
+        .iter()
+        .position(|c| *c == 'a' || *c == 'e')
+        .or_else(|| lower.windows(2).position(|w| w == ['o', 'u']))
+        .or_else(|| lower.iter().rposition(|c| "aeiouü".contains(*c)));
+    let Some(idx) = idx else {
+        return chars.into_iter().collect();
+    };
+
+    let marks = match chars[idx] {
+        'a' => "āáǎà",
+        'e' => "ēéěè",
+        'i' => "īíǐì",
+        'o' => "ōóǒò",
+        'u' => "ūúǔù",
+        'ü' => "ǖǘǚǜ",
+        'A' => "ĀÁǍÀ",
+        'E' => "ĒÉĚÈ",
+        'I' => "ĪÍǏÌ",
+        'O' => "ŌÓǑÒ",
+        'U' => "ŪÚǓÙ",
+        'Ü' => "ǕǗǙǛ",
+        _ => return chars.into_iter().collect(),
+    };
+    let mut chars = chars;
+    chars[idx] = marks.chars().nth(tone as usize - 1).unwrap_or(chars[idx]);
+    chars.into_iter().collect()
+}
+
+/// `u:` and `v` written for `ü`, the latter only after `l`/`n` (`lv4`, `nve4`) so other
+/// latin words in CEDICT keep their `v`.
+fn umlaut(syllable: &str) -> String {
+    let syllable = syllable.replace("u:", "ü").replace("U:", "Ü");
+    let mut umlauted = String::with_capacity(syllable.len());
+    let mut prev = ' ';
+    for c in syllable.chars() {
+        umlauted.push(match c {
+            'v' if "lnLN".contains(prev) => 'ü',
+            'V' if "lnLN".contains(prev) => 'Ü',
+            c => c,
+        });
+        prev = c;
+    }
+    umlauted
+}
+
+/// All substrings up to [MAX_WORD_LEN] characters, candidates for segmentation.
+fn substrings(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut subs = HashSet::new();
+    for start in 0..chars.len() {
+        for end in start + 1..=chars.len().min(start + MAX_WORD_LEN) {
+            subs.insert(chars[start..end].iter().collect::<String>());
+        }
+    }
+    subs.into_iter().collect()
+}
+
+/// Forward maximum matching: take the longest known word at each position,
+/// unknown characters become segments of their own.
+fn segment<F>(text: &str, known: F) -> Vec<String>
+where
+    F: Fn(&str) -> bool,
+{
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = vec![];
+    let mut start = 0;
+    while start < chars.len() {
+        let longest = (start + 1..=chars.len().min(start + MAX_WORD_LEN))
+            .rev()
+            .find(|&end| known(&chars[start..end].iter().collect::<String>()))
+            .unwrap_or(start + 1);
+        segments.push(chars[start..longest].iter().collect());
+        start = longest;
+    }
+    segments
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_line, segment, tone_marks, CedictLine};
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("雞蛋 鸡蛋 [ji1 dan4] /(chicken) egg/hen's egg/CL:個|个[ge4],打[da2]/"),
+            Some(CedictLine {
+                traditional: "雞蛋".to_string(),
+                simplified: "鸡蛋".to_string(),
+                pinyin: "ji1 dan4".to_string(),
+                definitions: vec![
+                    "(chicken) egg".to_string(),
+                    "hen's egg".to_string(),
+                    "CL:個|个[ge4],打[da2]".to_string()
+                ],
+            })
+        );
+        assert_eq!(parse_line("# CC-CEDICT"), None);
+    }
+
+    #[test]
+    fn test_tone_marks() {
+        assert_eq!(tone_marks("ji1 dan4"), "jī dàn");
+        assert_eq!(tone_marks("nu:3 er2"), "nǚ ér");
+        assert_eq!(tone_marks("gou3 liu2 xue2"), "gǒu liú xué");
+        assert_eq!(tone_marks("Bei3 jing1"), "Běi jīng");
+        assert_eq!(tone_marks("Ou1 zhou1"), "Ōu zhōu");
+        assert_eq!(tone_marks("ma5 A1 Q"), "ma Ā Q");
+        assert_eq!(tone_marks("Lv4 nve4"), "Lǜ nüè");
+        assert_eq!(tone_marks("Ou1"), "Ōu");
+        assert_eq!(tone_marks("LU:E4 NV3 YI1 WU3"), "LÜÈ NǙ YĪ WǓ");
+        assert_eq!(tone_marks("DVD vs"), "DVD vs");
+    }
+
+    #[test]
+    fn test_segment() {
+        let known = ["我", "喜欢", "喜", "吃", "鸡蛋", "鸡"];
+        assert_eq!(
+            segment("我喜欢吃鸡蛋x", |w| known.contains(&w)),
+            vec!["我", "喜欢", "吃", "鸡蛋", "x"]
+        );
+    }
+}
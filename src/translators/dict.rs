@@ -0,0 +1,511 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    time::timeout,
+};
+
+use crate::{
+    errors::Error,
+    utils::{
+        dyer::{Colors, Dye},
+        env_loader,
+    },
+};
+
+use super::{Lang, Translator};
+
+const DICT_SERVER: &str = "localhost:2628";
+const DICT_DATABASE: &str = "*";
+const DICT_STRATEGY: &str = "lev";
+/// How long the server may take to accept the connection or send a line.
+const DICT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client of the DICT protocol (RFC 2229), e.g. a `dictd` server.
+///
+/// Server, database and match strategy are taken from `RUNSLATE_DICT_SERVER`,
+/// `RUNSLATE_DICT_DATABASE` and `RUNSLATE_DICT_STRATEGY`.
+#[derive(Debug, Clone)]
+pub struct Dict;
+
+#[async_trait]
+impl Translator for Dict {
+    async fn translate(&self, words: &str, _source: &Lang, _target: &Lang) -> Result<Value, Error> {
+        trace!("Dict: Start to query server.");
+
+        let server = server();
+        let database = env_loader::load_or_default("RUNSLATE_DICT_DATABASE", DICT_DATABASE);
+        let strategy = env_loader::load_or_default("RUNSLATE_DICT_STRATEGY", DICT_STRATEGY);
+        let word = words.trim();
+        debug!(
+            "server: {}, database: {}, strategy: {}",
+            server, database, strategy
+        );
+
+        let mut client = DictClient::connect(&server).await?;
+        let definitions = client.define(&database, word).await?;
+        // suggest similar words only when nothing is defined
+        let matches = if definitions.is_empty() {
+            client.match_words(&database, &strategy, word).await?
+        } else {
+            vec![]
+        };
+        client.quit().await?;
+
+        Ok(json!({
+            "word": word,
+            "definitions": definitions,
+            "matches": matches,
+        }))
+    }
+
+    fn show(&self, response: &Value, more: bool) {
+        trace!("Dict: parsing response data.");
+
+        if let Some(Value::Array(definitions)) = response.get("definitions") {
+            for (idx, definition) in definitions.iter().enumerate() {
+                if let Some(Value::String(desc)) = definition.get("description") {
+                    println!("{}", desc.dye(Colors::Blue));
+                }
+                if let Some(Value::String(text)) = definition.get("text") {
+                    println!("{}", text.trim_end().dye(Colors::BrightWhite));
+                }
+                // only the first definition is printed in brief mode
+                if !more || idx == definitions.len() - 1 {
+                    break;
+                }
+                println!();
+            }
+        }
+
+        if let Some(Value::Array(matches)) = response.get("matches") {
+            if !matches.is_empty() {
+                let mut words: Vec<&str> = vec![];
+                for m in matches {
+                    if let Some(Value::String(word)) = m.get("word") {
+                        if !words.contains(&word.as_str()) {
+                            words.push(word);
+                        }
+                    }
+                }
+                println!("{}", "Did you mean:".dye(Colors::Blue));
+                println!("{}", words.join(", ").dye(Colors::Cyan));
+            }
+        }
+
+        trace!("Response parsed.");
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Definition {
+    pub word: String,
+    pub database: String,
+    pub description: String,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    pub database: String,
+    pub word: String,
+}
+
+/// `name "description"` pairs returned by `SHOW DB` and `SHOW STRAT`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+    pub description: String,
+}
+
+/// A connection to a DICT server.
+pub struct DictClient {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl DictClient {
+    /// Connect to `addr` (`host:port`) and consume the `220` banner.
+    pub async fn connect(addr: &str) -> Result<Self, Error> {
+        let stream = timed(format!("Connect to {addr}"), TcpStream::connect(addr)).await?;
+        let (reader, writer) = stream.into_split();
+        let mut client = DictClient {
+            reader: BufReader::new(reader),
+            writer,
+        };
+        let (code, banner) = client.read_status().await?;
+        debug!("banner: {} {}", code, banner);
+        if code != 220 {
+            return Err(Error::DictServerError(format!("{code} {banner}")));
+        }
+        Ok(client)
+    }
+
+    /// `DEFINE database word`, an empty list means no match.
+    pub async fn define(&mut self, database: &str, word: &str) -> Result<Vec<Definition>, Error> {
+        self.send(&format!("DEFINE {} {}", quote(database), quote(word)))
+            .await?;
+        let (code, text) = self.read_status().await?;
+        match code {
+            150 => {}
+            552 => return Ok(vec![]),
+            _ => return Err(Error::DictServerError(format!("{code} {text}"))),
+        }
+
+        let mut definitions = vec![];
+        loop {
+            let (code, text) = self.read_status().await?;
+            match code {
+                151 => {
+                    let mut args = split_args(&text).into_iter();
+                    let word = args.next().unwrap_or_default();
+                    let database = args.next().unwrap_or_default();
+                    let description = args.next().unwrap_or_default();
+                    let text = self.read_text().await?.join("\n");
+                    definitions.push(Definition {
+                        word,
+                        database,
+                        description,
+                        text,
+                    });
+                }
+                250 => return Ok(definitions),
+                _ => return Err(Error::DictServerError(format!("{code} {text}"))),
+            }
+        }
+    }
+
+    /// `MATCH database strategy word`, an empty list means no match.
+    pub async fn match_words(
+        &mut self,
+        database: &str,
+        strategy: &str,
+        word: &str,
+    ) -> Result<Vec<Match>, Error> {
+        self.send(&format!(
+            "MATCH {} {} {}",
+            quote(database),
+            quote(strategy),
+            quote(word)
+        ))
+        .await?;
+        let (code, text) = self.read_status().await?;
+        match code {
+            152 => {}
+            552 => return Ok(vec![]),
+            _ => return Err(Error::DictServerError(format!("{code} {text}"))),
+        }
+
+        let matches = self
+            .read_text()
+            .await?
+            .iter()
+            .map(|line| {
+                let mut args = split_args(line).into_iter();
+                Match {
+                    database: args.next().unwrap_or_default(),
+                    word: args.next().unwrap_or_default(),
+                }
+            })
+            .collect();
+        self.expect_ok().await?;
+        Ok(matches)
+    }
+
+    /// `SHOW DB`
+    pub async fn show_db(&mut self) -> Result<Vec<Item>, Error> {
+        self.show("SHOW DB", 110, 554).await
+    }
+
+    /// `SHOW STRAT`
+    pub async fn show_strat(&mut self) -> Result<Vec<Item>, Error> {
+        self.show("SHOW STRAT", 111, 555).await
+    }
+
+    pub async fn quit(&mut self) -> Result<(), Error> {
+        self.send("QUIT").await?;
+        let (code, text) = self.read_status().await?;
+        debug!("quit: {} {}", code, text);
+        Ok(())
+    }
+
+    async fn show(&mut self, command: &str, found: u16, empty: u16) -> Result<Vec<Item>, Error> {
+        self.send(command).await?;
+        let (code, text) = self.read_status().await?;
+        if code == empty {
+            return Ok(vec![]);
+        }
+        if code != found {
+            return Err(Error::DictServerError(format!("{code} {text}")));
+        }
+
+        let items = self
+            .read_text()
+            .await?
+            .iter()
+            .map(|line| {
+                let mut args = split_args(line).into_iter();
+                Item {
+                    name: args.next().unwrap_or_default(),
+                    description: args.next().unwrap_or_default(),
+                }
+            })
+            .collect();
+        self.expect_ok().await?;
+        Ok(items)
+    }
+
+    async fn send(&mut self, command: &str) -> Result<(), Error> {
+        debug!("dict >> {}", command);
+        self.writer
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> Result<String, Error> {
+        let mut line = String::new();
+        if timed("Read response", self.reader.read_line(&mut line)).await? == 0 {
+            return Err(Error::DictServerError(
+                "Connection closed by server".to_string(),
+            ));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Read a status line like `250 ok`.
+    async fn read_status(&mut self) -> Result<(u16, String), Error> {
+        let line = self.read_line().await?;
+        debug!("dict << {}", line);
+        let (code, text) = line.split_once(' ').unwrap_or((&line, ""));
+        match code.parse::<u16>() {
+            Ok(code) => Ok((code, text.to_string())),
+            Err(_) => Err(Error::DictServerError(format!("Bad status line: {line}"))),
+        }
+    }
+
+    /// Read a text block terminated by a single `.`, undoing dot-stuffing.
+    async fn read_text(&mut self) -> Result<Vec<String>, Error> {
+        let mut lines = vec![];
+        loop {
+            let line = self.read_line().await?;
+            if line == "." {
+                return Ok(lines);
+            }
+            match line.strip_prefix("..") {
+                Some(rest) => lines.push(format!(".{rest}")),
+                None => lines.push(line),
+            }
+        }
+    }
+
+    async fn expect_ok(&mut self) -> Result<(), Error> {
+        match self.read_status().await? {
+            (250, _) => Ok(()),
+            (code, text) => Err(Error::DictServerError(format!("{code} {text}"))),
+        }
+    }
+}
+
+/// Await `io`, failing with `what` timed out if the server doesn't answer within [DICT_TIMEOUT].
+async fn timed<T, F>(what: impl std::fmt::Display, io: F) -> Result<T, Error>
+where
+    F: Future<Output = std::io::Result<T>>,
+{
+    match timeout(DICT_TIMEOUT, io).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(Error::DictServerError(format!(
+            "{what} timed out after {}s",
+            DICT_TIMEOUT.as_secs()
+        ))),
+    }
+}
+
+/// Quote a command parameter if it contains spaces or quotes.
+fn quote(param: &str) -> String {
+    if !param.is_empty() && !param.contains([' ', '"', '\'', '\\']) {
+        return param.to_string();
+    }
+    format!("\"{}\"", param.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Split a response line into words, honoring double quotes.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => arg.extend(chars.next()),
+                    _ => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+        }
+        args.push(arg);
+    }
+    args
+}
+
+fn server() -> String {
+    env_loader::load_or_default("RUNSLATE_DICT_SERVER", DICT_SERVER)
+}
+
+/// List databases on the DICT server.
+pub async fn databases() -> Result<(), Error> {
+    let mut client = DictClient::connect(&server()).await?;
+    let items = client.show_db().await?;
+    client.quit().await?;
+    print_items(&items);
+    Ok(())
+}
+
+/// List match strategies on the DICT server.
+pub async fn strategies() -> Result<(), Error> {
+    let mut client = DictClient::connect(&server()).await?;
+    let items = client.show_strat().await?;
+    client.quit().await?;
+    print_items(&items);
+    Ok(())
+}
+
+/// Print words matching `word` with `strategy`.
+pub async fn suggest(word: &str, database: &str, strategy: &str) -> Result<(), Error> {
+    let mut client = DictClient::connect(&server()).await?;
+    let matches = client.match_words(database, strategy, word).await?;
+    client.quit().await?;
+    for m in matches {
+        println!("{} {}", m.database.dye(Colors::Blue), m.word);
+    }
+    Ok(())
+}
+
+fn print_items(items: &[Item]) {
+    let width = items.iter().map(|i| i.name.len()).max().unwrap_or(0);
+    for item in items {
+        println!(
+            "{} {}",
+            format!("{: <width$}", item.name).dye(Colors::Blue),
+            item.description
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::{quote, split_args, DictClient};
+
+    /// A tiny dictd stand-in answering one connection with canned responses.
+    async fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            writer
+                .write_all(b"220 test dictd <auth.mime> <1@test>\r\n")
+                .await
+                .unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let reply = match line.as_str() {
+                    "DEFINE * hello" => concat!(
+                        "150 1 definitions retrieved\r\n",
+                        "151 \"hello\" wn \"WordNet (r) 3.0 (2006)\"\r\n",
+                        "hello\r\n",
+                        "    n 1: an expression of greeting\r\n",
+                        "..dotted\r\n",
+                        ".\r\n",
+                        "250 ok\r\n"
+                    ),
+                    "DEFINE * \"no such\"" => "552 no match\r\n",
+                    "MATCH * lev helo" => concat!(
+                        "152 2 matches found\r\n",
+                        "wn \"hello\"\r\n",
+                        "jargon \"hello, world\"\r\n",
+                        ".\r\n",
+                        "250 ok\r\n"
+                    ),
+                    "SHOW DB" => concat!(
+                        "110 2 databases present\r\n",
+                        "wn \"WordNet (r) 3.0 (2006)\"\r\n",
+                        "jargon \"The Jargon File\"\r\n",
+                        ".\r\n",
+                        "250 ok\r\n"
+                    ),
+                    "QUIT" => "221 bye\r\n",
+                    _ => "500 unknown command\r\n",
+                };
+                writer.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let addr = serve().await;
+        let mut client = DictClient::connect(&addr).await.unwrap();
+
+        let definitions = client.define("*", "hello").await.unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].database, "wn");
+        assert_eq!(definitions[0].description, "WordNet (r) 3.0 (2006)");
+        assert_eq!(
+            definitions[0].text,
+            "hello\n    n 1: an expression of greeting\n.dotted"
+        );
+
+        assert!(client.define("*", "no such").await.unwrap().is_empty());
+
+        let matches = client.match_words("*", "lev", "helo").await.unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].word, "hello, world");
+
+        let databases = client.show_db().await.unwrap();
+        assert_eq!(databases[1].name, "jargon");
+        assert_eq!(databases[1].description, "The Jargon File");
+
+        assert!(client.show_strat().await.is_err());
+        client.quit().await.unwrap();
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("hello"), "hello");
+        assert_eq!(quote("look up"), "\"look up\"");
+        assert_eq!(quote("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(
+            split_args("\"say \\\"hi\\\"\" wn \"WordNet\""),
+            vec!["say \"hi\"", "wn", "WordNet"]
+        );
+    }
+}
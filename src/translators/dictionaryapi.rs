@@ -49,7 +49,7 @@ impl Translator for DictionaryApi {
                             if let Value::Object(pobj) = p {
                                 if let Some(Value::String(text)) = pobj.get("text") {
                                     phonetic_str.push_str(text);
-                                    phonetic_str.push_str(" ");
+                                    phonetic_str.push(' ');
                                 }
                             }
                         }
@@ -0,0 +1,353 @@
+//! ECDICT's companion datasets, shipped next to `stardict.csv`:
+//!
+//! - `lemma.en.txt`: `abandon/1234 -> abandoned,abandoning,abandons`, `;` starts a comment
+//! - `resemble.txt`: near-synonym groups, `% abandon, desert, forsake` followed by usage notes
+//! - `wordroot.txt`: a JSON object of roots and affixes,
+//!   `{"spect": {"class": "root", "origin": "...", "meaning": "看", "example": ["inspect"]}}`
+
+use std::{collections::HashMap, fs, path::Path};
+
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::errors::Error;
+
+use super::entities::{ecdict_lemmas, resemble_groups, resemble_words, wordroot_words, wordroots};
+
+/// Parse a line of `lemma.en.txt` into the lemma and its forms, `None` for comments.
+pub fn parse_lemma_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+        return None;
+    }
+    // drop the frequency count, e.g. `abandon/1234`
+    let strip = |w: &str| w.split('/').next().unwrap_or("").trim().to_string();
+    let (lemma, forms) = line.split_once("->")?;
+    let forms = forms
+        .split(',')
+        .map(strip)
+        .filter(|f| !f.is_empty())
+        .collect();
+    Some((strip(lemma), forms))
+}
+
+/// A near-synonym discrimination group of `resemble.txt`.
+#[derive(Debug, PartialEq)]
+pub struct ResembleGroup {
+    pub words: Vec<String>,
+    pub content: String,
+}
+
+/// Parse `resemble.txt`, each group starts with a `%` line listing its words.
+pub fn parse_resemble(text: &str) -> Vec<ResembleGroup> {
+    let mut groups: Vec<ResembleGroup> = vec![];
+    for line in text.lines() {
+        if let Some(words) = line.strip_prefix('%') {
+            groups.push(ResembleGroup {
+                words: words
+                    .split([',', '，'])
+                    .map(|w| w.trim().to_string())
+                    .filter(|w| !w.is_empty())
+                    .collect(),
+                content: String::new(),
+            });
+        } else if let Some(group) = groups.last_mut() {
+            group.content.push_str(line.trim_end());
+            group.content.push('\n');
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|g| !g.words.is_empty())
+        .map(|g| ResembleGroup {
+            content: g.content.trim().to_string(),
+            ..g
+        })
+        .collect()
+}
+
+/// A root or affix of `wordroot.txt`.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+pub struct WordRoot {
+    #[serde(skip)]
+    pub root: String,
+    pub class: Option<String>,
+    pub origin: Option<String>,
+    pub meaning: Option<String>,
+    #[serde(default, alias = "examples")]
+    pub example: Vec<String>,
+}
+
+/// Parse `wordroot.txt`, entries which aren't objects are skipped.
+pub fn parse_wordroots(text: &str) -> Result<Vec<WordRoot>, Error> {
+    let roots: serde_json::Map<String, Value> = serde_json::from_str(text)?;
+    Ok(roots
+        .into_iter()
+        .filter_map(|(root, value)| {
+            let root_value = serde_json::from_value::<WordRoot>(value).ok()?;
+            Some(WordRoot { root, ..root_value })
+        })
+        .collect())
+}
+
+/// Replace the `ecdict_lemmas` table with `lemma.en.txt`, returns the number of forms.
+pub async fn import_lemmas(db: &DatabaseConnection, path: &Path) -> Result<u64, Error> {
+    let text = fs::read_to_string(path)?;
+    let models = text
+        .lines()
+        .filter_map(parse_lemma_line)
+        .flat_map(|(lemma, forms)| {
+            forms
+                .into_iter()
+                .filter(|f| *f != lemma)
+                .map(|form| (form, lemma.clone()))
+                .collect::<Vec<_>>()
+        })
+        .map(|(form, lemma)| ecdict_lemmas::ActiveModel {
+            form: Set(form),
+            lemma: Set(lemma),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    let count = models.len() as u64;
+    let txn = db.begin().await?;
+    ecdict_lemmas::Entity::delete_many().exec(&txn).await?;
+    for chunk in models.chunks(1000) {
+        ecdict_lemmas::Entity::insert_many(chunk.to_vec())
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+    Ok(count)
+}
+
+/// Replace the resemble tables with `resemble.txt`, returns the number of groups.
+pub async fn import_resemble(db: &DatabaseConnection, path: &Path) -> Result<u64, Error> {
+    let text = fs::read_to_string(path)?;
+    let groups = parse_resemble(&text);
+
+    let txn = db.begin().await?;
+    resemble_words::Entity::delete_many().exec(&txn).await?;
+    resemble_groups::Entity::delete_many().exec(&txn).await?;
+    for group in &groups {
+        let id = resemble_groups::Entity::insert(resemble_groups::ActiveModel {
+            words: Set(json!(group.words).to_string()),
+            content: Set(group.content.clone()),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?
+        .last_insert_id;
+        resemble_words::Entity::insert_many(group.words.iter().map(|word| {
+            resemble_words::ActiveModel {
+                word: Set(word.clone()),
+                group_id: Set(id),
+                ..Default::default()
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(groups.len() as u64)
+}
+
+/// Replace the word root tables with `wordroot.txt`, returns the number of roots.
+pub async fn import_wordroots(db: &DatabaseConnection, path: &Path) -> Result<u64, Error> {
+    let roots = parse_wordroots(&fs::read_to_string(path)?)?;
+
+    let txn = db.begin().await?;
+    wordroot_words::Entity::delete_many().exec(&txn).await?;
+    wordroots::Entity::delete_many().exec(&txn).await?;
+    for root in &roots {
+        let id = wordroots::Entity::insert(wordroots::ActiveModel {
+            root: Set(root.root.clone()),
+            class: Set(root.class.clone()),
+            origin: Set(root.origin.clone()),
+            meaning: Set(root.meaning.clone()),
+            examples: Set(json!(root.example).to_string()),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?
+        .last_insert_id;
+        if root.example.is_empty() {
+            continue;
+        }
+        wordroot_words::Entity::insert_many(root.example.iter().map(|word| {
+            wordroot_words::ActiveModel {
+                word: Set(word.clone()),
+                root_id: Set(id),
+                ..Default::default()
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(roots.len() as u64)
+}
+
+/// Lemmas of `form` listed in `lemma.en.txt`.
+pub async fn lemmas(db: &DatabaseConnection, form: &str) -> Result<Vec<String>, DbErr> {
+    Ok(ecdict_lemmas::Entity::find()
+        .filter(ecdict_lemmas::Column::Form.eq(form))
+        .order_by_asc(ecdict_lemmas::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|l| l.lemma)
+        .collect())
+}
+
+/// Discrimination groups `word` belongs to, as `{"words": [...], "content": "..."}`.
+pub async fn resemble(db: &DatabaseConnection, word: &str) -> Result<Vec<Value>, DbErr> {
+    let ids = resemble_words::Entity::find()
+        .filter(resemble_words::Column::Word.eq(word))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|w| w.group_id);
+    Ok(resemble_groups::Entity::find()
+        .filter(resemble_groups::Column::Id.is_in(ids))
+        .order_by_asc(resemble_groups::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(group_value)
+        .collect())
+}
+
+/// Roots and affixes `word` is an example of.
+pub async fn roots(db: &DatabaseConnection, word: &str) -> Result<Vec<Value>, DbErr> {
+    let ids = wordroot_words::Entity::find()
+        .filter(wordroot_words::Column::Word.eq(word))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|w| w.root_id);
+    Ok(wordroots::Entity::find()
+        .filter(wordroots::Column::Id.is_in(ids))
+        .order_by_asc(wordroots::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(root_value)
+        .collect())
+}
+
+/// Discrimination groups of every word, see [resemble].
+pub async fn resemble_by_word(
+    db: &DatabaseConnection,
+) -> Result<HashMap<String, Vec<Value>>, DbErr> {
+    let groups = resemble_groups::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|g| (g.id, group_value(g)))
+        .collect::<HashMap<i32, Value>>();
+    let mut by_word: HashMap<String, Vec<Value>> = HashMap::new();
+    for w in resemble_words::Entity::find()
+        .order_by_asc(resemble_words::Column::GroupId)
+        .all(db)
+        .await?
+    {
+        if let Some(group) = groups.get(&w.group_id) {
+            by_word.entry(w.word).or_default().push(group.clone());
+        }
+    }
+    Ok(by_word)
+}
+
+/// Roots and affixes of every example word, see [roots].
+pub async fn roots_by_word(db: &DatabaseConnection) -> Result<HashMap<String, Vec<Value>>, DbErr> {
+    let roots = wordroots::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| (r.id, root_value(r)))
+        .collect::<HashMap<i32, Value>>();
+    let mut by_word: HashMap<String, Vec<Value>> = HashMap::new();
+    for w in wordroot_words::Entity::find()
+        .order_by_asc(wordroot_words::Column::RootId)
+        .all(db)
+        .await?
+    {
+        if let Some(root) = roots.get(&w.root_id) {
+            by_word.entry(w.word).or_default().push(root.clone());
+        }
+    }
+    Ok(by_word)
+}
+
+fn group_value(group: resemble_groups::Model) -> Value {
+    json!({
+        "words": serde_json::from_str::<Value>(&group.words).unwrap_or_default(),
+        "content": group.content,
+    })
+}
+
+fn root_value(root: wordroots::Model) -> Value {
+    json!({
+        "root": root.root,
+        "class": root.class,
+        "origin": root.origin,
+        "meaning": root.meaning,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_lemma_line, parse_resemble, parse_wordroots, ResembleGroup, WordRoot};
+
+    #[test]
+    fn test_parse_companions() {
+        assert_eq!(
+            parse_lemma_line("abandon/1234 -> abandoned,abandoning, abandons"),
+            Some((
+                "abandon".to_string(),
+                vec![
+                    "abandoned".to_string(),
+                    "abandoning".to_string(),
+                    "abandons".to_string()
+                ]
+            ))
+        );
+        assert_eq!(parse_lemma_line("; lemma list"), None);
+
+        let text = "% abandon, desert，forsake\n这些动词均含“放弃”之意。\nabandon: 指完全放弃。\n\n% \n%leave\n离开";
+        assert_eq!(
+            parse_resemble(text),
+            vec![
+                ResembleGroup {
+                    words: vec!["abandon".into(), "desert".into(), "forsake".into()],
+                    content: "这些动词均含“放弃”之意。\nabandon: 指完全放弃。".into(),
+                },
+                ResembleGroup {
+                    words: vec!["leave".into()],
+                    content: "离开".into(),
+                },
+            ]
+        );
+
+        let roots = parse_wordroots(
+            r#"{"spect": {"class": "root", "meaning": "看", "example": ["inspect"]}, "bad": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            roots,
+            vec![WordRoot {
+                root: "spect".into(),
+                class: Some("root".into()),
+                meaning: Some("看".into()),
+                example: vec!["inspect".into()],
+                ..Default::default()
+            }]
+        );
+    }
+}
@@ -2,4 +2,11 @@
 
 pub mod prelude;
 
+pub mod ecdict_imports;
+pub mod ecdict_lemmas;
+pub mod resemble_groups;
+pub mod resemble_words;
+pub mod word_forms;
+pub mod wordroot_words;
+pub mod wordroots;
 pub mod words;
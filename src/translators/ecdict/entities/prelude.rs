@@ -1,3 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+pub use super::ecdict_imports::Entity as EcdictImports;
+pub use super::ecdict_lemmas::Entity as EcdictLemmas;
+pub use super::resemble_groups::Entity as ResembleGroups;
+pub use super::resemble_words::Entity as ResembleWords;
+pub use super::word_forms::Entity as WordForms;
+pub use super::wordroot_words::Entity as WordrootWords;
+pub use super::wordroots::Entity as Wordroots;
 pub use super::words::Entity as Words;
@@ -33,6 +33,10 @@ pub struct Model {
     pub detail: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub audio: Option<String>,
+    /// Stripped headword for lookups, not part of the response
+    #[sea_orm(column_type = "Text", nullable)]
+    #[serde(skip)]
+    pub sw: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,500 @@
+//! Compiled ECDICT index, the `fst` storage engine.
+//!
+//! Built from the SQLite database into `<db>.index/` by `runslate ecdict index`:
+//!
+//! - `words.fst`: headwords and inflected forms to the location of their record
+//! - `stripped.fst`: the same keyed by [strip_word], for case and punctuation variants
+//! - `records.zst`: zstd frames of [FRAME_RECORDS] JSON lines, each behind its `u32` length
+//!
+//! A location is the frame's offset shifted left by 8 bits plus the line in the frame, so a
+//! lookup is two memory-mapped FST walks and one small frame decompressed.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use fst::{automaton::Levenshtein, Automaton, IntoStreamer, Map, MapBuilder, Streamer};
+use memmap2::Mmap;
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{errors::Error, translators::Lang};
+
+use super::{
+    check_words, companion, connect_db, database_path,
+    entities::{ecdict_lemmas, word_forms, words},
+    entry_value, lemma, merge_lemmas, segment_phrases, strip_word, suggest, SUGGEST_LIMIT,
+};
+
+const INDEX_HINT: &str = "build it with `runslate ecdict index`";
+
+const WORDS_FILE: &str = "words.fst";
+const STRIPPED_FILE: &str = "stripped.fst";
+const RECORDS_FILE: &str = "records.zst";
+
+/// Records per zstd frame, at most 256 to fit the line number in a location.
+const FRAME_RECORDS: usize = 32;
+
+const ZSTD_LEVEL: i32 = 9;
+
+/// Rows fetched from SQLite at a time while building.
+const BUILD_PAGE: u64 = 5000;
+
+/// Fuzzy matches looked at before ranking suggestions by frequency.
+const FUZZY_CANDIDATES: usize = 200;
+
+/// What the SQLite engine gathers from several tables, stored together.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Record {
+    /// `None` for inflected forms without a row of their own.
+    pub model: Option<words::Model>,
+    /// `(lemma, kind)` pairs, see [lemma::lemmas].
+    #[serde(default)]
+    pub lemmas: Vec<(String, String)>,
+    #[serde(default)]
+    pub roots: Vec<Value>,
+    #[serde(default)]
+    pub resemble: Vec<Value>,
+}
+
+pub struct FstIndex {
+    words: Map<Mmap>,
+    stripped: Map<Mmap>,
+    records: Mmap,
+}
+
+/// Build the index of the default database, `runslate ecdict index`.
+pub async fn index() -> Result<(), Error> {
+    let dir = index_dir()
+        .ok_or_else(|| Error::Database("An in-memory database can't be indexed".to_string()))?;
+    let db = connect_db().await?;
+    check_words(&db).await?;
+    let count = build(&db, &dir).await?;
+    println!("Indexed {} records into {}.", count, dir.display());
+    Ok(())
+}
+
+/// Print headwords starting with `prefix`, or close to it with `fuzzy`, one per line.
+pub fn complete(prefix: &str, limit: usize, fuzzy: bool) -> Result<(), Error> {
+    let index = FstIndex::open_fresh()?.ok_or_else(|| {
+        Error::Database(format!("ECDICT index is missing or stale, {INDEX_HINT}"))
+    })?;
+    let words = if fuzzy {
+        index.suggest(prefix, limit)?
+    } else {
+        index.complete(prefix, limit)
+    };
+    for word in words {
+        println!("{word}");
+    }
+    Ok(())
+}
+
+/// The index directory of the database, `None` for in-memory databases.
+pub fn index_dir() -> Option<PathBuf> {
+    database_path().map(|p| p.with_extension("index"))
+}
+
+impl FstIndex {
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        Ok(FstIndex {
+            words: Map::new(mmap(&dir.join(WORDS_FILE))?)?,
+            stripped: Map::new(mmap(&dir.join(STRIPPED_FILE))?)?,
+            records: mmap(&dir.join(RECORDS_FILE))?,
+        })
+    }
+
+    /// The index of the database, `None` if it's missing or older than the database.
+    pub fn open_fresh() -> Result<Option<Self>, Error> {
+        match index_dir() {
+            Some(dir) if suggest::is_fresh(&dir.join(RECORDS_FILE)) => Ok(Some(Self::open(&dir)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Record of `word`, falling back to case and punctuation variants like [super::query].
+    pub fn get(&self, word: &str) -> Result<Option<Record>, Error> {
+        let location = self
+            .words
+            .get(word)
+            .or_else(|| self.stripped.get(strip_word(word)));
+        location.map(|l| self.record(l)).transpose()
+    }
+
+    fn record(&self, location: u64) -> Result<Record, Error> {
+        let offset = (location >> 8) as usize;
+        let line = (location & 0xff) as usize;
+        let corrupted = || Error::Database(format!("ECDICT index has no record at {location}"));
+        let len = self
+            .records
+            .get(offset..offset + 4)
+            .and_then(|b| b.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or_else(corrupted)? as usize;
+        let frame = self
+            .records
+            .get(offset + 4..offset + 4 + len)
+            .ok_or_else(corrupted)?;
+        let frame = zstd::decode_all(frame)?;
+        let record = frame
+            .split(|b| *b == b'\n')
+            .nth(line)
+            .ok_or_else(corrupted)?;
+        Ok(serde_json::from_slice(record)?)
+    }
+
+    /// Keys starting with `prefix`, in byte order.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let matcher = fst::automaton::Str::new(prefix).starts_with();
+        let mut stream = self.words.search(matcher).into_stream();
+        let mut keys = vec![];
+        while let Some((key, _)) = stream.next() {
+            if keys.len() >= limit {
+                break;
+            }
+            keys.push(String::from_utf8_lossy(key).to_string());
+        }
+        keys
+    }
+
+    /// Headwords within the edit distance of `word`, closest and most frequent first.
+    pub fn suggest(&self, word: &str, limit: usize) -> Result<Vec<String>, Error> {
+        let word = word.to_lowercase();
+        // one edit for short words, two otherwise, like the BK-tree
+        let max = if word.chars().count() <= 4 { 1 } else { 2 };
+        let Ok(automaton) = Levenshtein::new(&word, max) else {
+            return Ok(vec![]);
+        };
+        let mut stream = self.words.search(automaton).into_stream();
+        let mut candidates = vec![];
+        while let Some((key, location)) = stream.next() {
+            if candidates.len() >= FUZZY_CANDIDATES {
+                break;
+            }
+            candidates.push((String::from_utf8_lossy(key).to_string(), location));
+        }
+
+        let mut ranked = vec![];
+        for (key, location) in candidates {
+            if key == word || !key.chars().all(|c| c.is_alphabetic() || c == '-') {
+                continue;
+            }
+            let Some(model) = self.record(location)?.model else {
+                continue;
+            };
+            let rank = suggest::rank(model.frq.as_deref(), model.bnc.as_deref());
+            ranked.push((suggest::levenshtein(&key, &word), rank, key));
+        }
+        ranked.sort();
+        Ok(ranked.into_iter().take(limit).map(|(_, _, w)| w).collect())
+    }
+
+    /// Same response as the SQLite engine for words and phrases.
+    pub fn translate(&self, words: &str, target: &Lang) -> Result<Value, Error> {
+        let tokens: Vec<&str> = words.split_whitespace().collect();
+        if tokens.len() <= 1 {
+            return self.lookup(tokens.first().copied().unwrap_or(""), target);
+        }
+
+        let phrase = tokens.join(" ");
+        if let Some(Record {
+            model: Some(model), ..
+        }) = self.get(&phrase)?
+        {
+            return entry_value(model, target);
+        }
+
+        let known = |p: &str| self.words.contains_key(p);
+        let mut entries = vec![];
+        for segment in segment_phrases(&tokens, known) {
+            entries.push(self.lookup(&segment, target)?);
+        }
+        Ok(json!({
+            "phrase": phrase,
+            "entries": entries,
+        }))
+    }
+
+    /// Exact record, suggestions or lemma of a single word, see [super::lookup_word].
+    pub fn lookup(&self, word: &str, target: &Lang) -> Result<Value, Error> {
+        let record = self.get(word)?.unwrap_or_default();
+        if record.model.is_none() && record.lemmas.is_empty() {
+            let suggestions = self.suggest(word, SUGGEST_LIMIT)?;
+            if let Some(best) = suggestions.first() {
+                if let Some(record) = self.get(best)?.filter(|r| r.model.is_some()) {
+                    let mut value = entry(record, target)?;
+                    value["query"] = json!(word);
+                    value["suggestions"] = json!(suggestions);
+                    return Ok(value);
+                }
+            }
+        }
+
+        let lemmas = record.lemmas.clone();
+        let mut value = match record.model {
+            Some(_) => entry(record, target)?,
+            None if lemmas.is_empty() => Value::Null,
+            None => json!({"word": word}),
+        };
+        let mut entries = vec![];
+        for (lemma, kind) in lemmas {
+            if let Some(record) = self.get(&lemma)?.filter(|r| r.model.is_some()) {
+                let mut entry = entry(record, target)?;
+                let labels = kind
+                    .chars()
+                    .filter_map(|c| lemma::kind_label(c, target))
+                    .collect::<Vec<&str>>();
+                entry["kind"] = json!(kind);
+                entry["kind_labels"] = json!(labels);
+                entries.push(entry);
+            }
+        }
+        if !entries.is_empty() {
+            value["lemmas"] = json!(entries);
+        }
+        Ok(value)
+    }
+}
+
+/// JSON of a record with a row, with word roots and near-synonym groups like [super::add_companions].
+fn entry(record: Record, target: &Lang) -> Result<Value, Error> {
+    let Some(model) = record.model else {
+        return Ok(Value::Null);
+    };
+    let mut value = entry_value(model, target)?;
+    if !record.roots.is_empty() {
+        value["roots"] = json!(record.roots);
+    }
+    if !record.resemble.is_empty() {
+        value["resemble"] = json!(record.resemble);
+    }
+    Ok(value)
+}
+
+fn mmap(path: &Path) -> Result<Mmap, Error> {
+    let file =
+        File::open(path).map_err(|e| Error::OpenFileError(format!("{}: {}", path.display(), e)))?;
+    // the index is only ever replaced as a whole directory, never written in place
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+/// Appends records to `records.zst` a frame at a time.
+struct RecordWriter {
+    file: BufWriter<File>,
+    offset: u64,
+    frame: Vec<u8>,
+    lines: usize,
+}
+
+impl RecordWriter {
+    fn create(path: &Path) -> Result<Self, Error> {
+        Ok(RecordWriter {
+            file: BufWriter::new(File::create(path)?),
+            offset: 0,
+            frame: vec![],
+            lines: 0,
+        })
+    }
+
+    /// Append a record and return its location.
+    fn push(&mut self, record: &Record) -> Result<u64, Error> {
+        let location = (self.offset << 8) | self.lines as u64;
+        if self.lines > 0 {
+            self.frame.push(b'\n');
+        }
+        serde_json::to_writer(&mut self.frame, record)?;
+        self.lines += 1;
+        if self.lines == FRAME_RECORDS {
+            self.flush()?;
+        }
+        Ok(location)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.lines == 0 {
+            return Ok(());
+        }
+        let compressed = zstd::encode_all(self.frame.as_slice(), ZSTD_LEVEL)?;
+        self.file
+            .write_all(&(compressed.len() as u32).to_le_bytes())?;
+        self.file.write_all(&compressed)?;
+        self.offset += 4 + compressed.len() as u64;
+        self.frame.clear();
+        self.lines = 0;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        self.flush()?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Build the index of the database into `dir`, replacing it, returns the number of records.
+pub async fn build(db: &DatabaseConnection, dir: &Path) -> Result<u64, Error> {
+    let mut forms: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for form in word_forms::Entity::find()
+        .order_by_asc(word_forms::Column::Id)
+        .all(db)
+        .await?
+    {
+        forms
+            .entry(form.form)
+            .or_default()
+            .push((form.lemma, form.kind));
+    }
+    let mut companion_lemmas: HashMap<String, Vec<String>> = HashMap::new();
+    for lemma in ecdict_lemmas::Entity::find()
+        .order_by_asc(ecdict_lemmas::Column::Id)
+        .all(db)
+        .await?
+    {
+        companion_lemmas
+            .entry(lemma.form)
+            .or_default()
+            .push(lemma.lemma);
+    }
+    let mut roots = companion::roots_by_word(db).await?;
+    let mut resemble = companion::resemble_by_word(db).await?;
+
+    let building = dir.with_extension("building");
+    if building.exists() {
+        fs::remove_dir_all(&building)?;
+    }
+    fs::create_dir_all(&building)?;
+    let mut writer = RecordWriter::create(&building.join(RECORDS_FILE))?;
+    let mut keys: Vec<(String, u64)> = vec![];
+
+    let mut pages = words::Entity::find()
+        .order_by_asc(words::Column::Id)
+        .paginate(db, BUILD_PAGE);
+    while let Some(models) = pages.fetch_and_next().await? {
+        for model in models {
+            let word = model.word.clone();
+            let mut lemmas = forms.remove(&word).unwrap_or_default();
+            merge_lemmas(
+                &mut lemmas,
+                &word,
+                model.exchange.as_deref(),
+                companion_lemmas.remove(&word).unwrap_or_default(),
+            );
+            let record = Record {
+                model: Some(model),
+                lemmas,
+                roots: roots.remove(&word).unwrap_or_default(),
+                resemble: resemble.remove(&word).unwrap_or_default(),
+            };
+            keys.push((word, writer.push(&record)?));
+        }
+        eprint!("\rIndexing: {} records", keys.len());
+    }
+
+    // inflected forms without a row of their own only point to their lemmas
+    for (form, lemmas) in companion_lemmas {
+        let entry = forms.entry(form.clone()).or_default();
+        merge_lemmas(entry, &form, None, lemmas);
+    }
+    let mut forms = forms.into_iter().collect::<Vec<_>>();
+    forms.sort();
+    for (form, lemmas) in forms {
+        let record = Record {
+            lemmas,
+            ..Default::default()
+        };
+        keys.push((form, writer.push(&record)?));
+    }
+    writer.finish()?;
+    eprintln!("\rIndexing: {} records", keys.len());
+
+    let count = keys.len() as u64;
+    let stripped = keys
+        .iter()
+        .map(|(key, location)| (strip_word(key), *location))
+        .collect();
+    write_map(&building.join(WORDS_FILE), keys)?;
+    write_map(&building.join(STRIPPED_FILE), stripped)?;
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::rename(&building, dir)?;
+    Ok(count)
+}
+
+/// Write keys in byte order, the first location of a duplicated key wins.
+fn write_map(path: &Path, mut keys: Vec<(String, u64)>) -> Result<(), Error> {
+    // stable, so duplicates keep their build order
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    keys.dedup_by(|later, first| later.0 == first.0);
+    let mut builder = MapBuilder::new(BufWriter::new(File::create(path)?))?;
+    for (key, location) in keys.into_iter().filter(|(k, _)| !k.is_empty()) {
+        builder.insert(key, location)?;
+    }
+    builder.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+
+    use super::{build, FstIndex};
+    use crate::translators::{ecdict::import::import_csv, Lang};
+
+    #[tokio::test]
+    async fn test_fst_index() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "runslate_test_fst_index_{}.csv",
+            std::process::id()
+        ));
+        let mut csv = "word,phonetic,definition,translation,pos,collins,oxford,tag,bnc,frq,exchange,detail,audio\n\
+            go,,,vi. 去,,,,,,,p:went/3:goes,,\nwent,,,v. go的过去式,,,,,,,0:go/1:p,,\nE-mail,,,n. 电子邮件,,,,,,,,,\n"
+            .to_string();
+        // more than a frame of records
+        for i in 0..40u8 {
+            let word = format!("word{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char);
+            csv.push_str(&format!("{word},,,n. 词{i},,,,,,,,,\n"));
+        }
+        std::fs::write(&path, &csv).unwrap();
+        import_csv(&db, &path, true).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let dir =
+            std::env::temp_dir().join(format!("runslate_test_fst_{}.index", std::process::id()));
+        // 43 rows and `goes`, which only points to its lemma
+        assert_eq!(build(&db, &dir).await.unwrap(), 44);
+        let index = FstIndex::open(&dir).unwrap();
+
+        let word = |w: &str| index.get(w).unwrap().and_then(|r| r.model).map(|m| m.word);
+        assert_eq!(word("wordbn").as_deref(), Some("wordbn"));
+        assert_eq!(word("email").as_deref(), Some("E-mail"));
+        assert_eq!(word("gone"), None);
+
+        let goes = index.lookup("goes", &Lang::Zh).unwrap();
+        assert_eq!(goes["lemmas"][0]["word"], "go");
+        assert_eq!(goes["lemmas"][0]["kind"], "3");
+        let went = index.lookup("went", &Lang::Zh).unwrap();
+        assert_eq!(went["translation"], "v. go的过去式");
+        assert_eq!(went["lemmas"][0]["word"], "go");
+
+        assert_eq!(
+            index.complete("wordb", 3),
+            vec!["wordba", "wordbb", "wordbc"]
+        );
+        assert_eq!(index.suggest("wrdab", 1).unwrap(), vec!["wordab"]);
+        let miss = index.lookup("wrodaa", &Lang::Zh).unwrap();
+        assert_eq!(miss["word"], "wordaa");
+        assert_eq!(miss["query"], "wrodaa");
+
+        drop(index);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! `runslate ecdict import`: load ECDICT's `stardict.csv` and its companion datasets
+//! (see [companion]) into the database.
+//!
+//! Rows are upserted by headword in batches. Each file is recorded by checksum in
+//! `ecdict_imports`, so an unchanged file is skipped and an interrupted import resumes
+//! after its last committed batch.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection,
+    EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, TransactionTrait,
+};
+
+use crate::errors::Error;
+
+use super::{
+    companion, connect_db_writable,
+    entities::{ecdict_imports, words},
+    fst_index, lemma, strip_word, suggest, Engine,
+};
+
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, serde::Deserialize)]
+struct CsvRecord {
+    word: String,
+    phonetic: Option<String>,
+    definition: Option<String>,
+    translation: Option<String>,
+    pos: Option<String>,
+    collins: Option<String>,
+    oxford: Option<String>,
+    tag: Option<String>,
+    bnc: Option<String>,
+    frq: Option<String>,
+    exchange: Option<String>,
+    detail: Option<String>,
+    audio: Option<String>,
+}
+
+impl From<CsvRecord> for words::ActiveModel {
+    fn from(record: CsvRecord) -> Self {
+        words::ActiveModel {
+            sw: Set(Some(strip_word(&record.word))),
+            word: Set(record.word),
+            phonetic: Set(record.phonetic),
+            definition: Set(record.definition),
+            translation: Set(record.translation),
+            pos: Set(record.pos),
+            collins: Set(record.collins),
+            oxford: Set(record.oxford),
+            tag: Set(record.tag),
+            bnc: Set(record.bnc),
+            frq: Set(record.frq),
+            exchange: Set(record.exchange),
+            detail: Set(record.detail),
+            audio: Set(record.audio),
+            ..Default::default()
+        }
+    }
+}
+
+/// Datasets `runslate ecdict import` takes.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Dataset {
+    /// `stardict.csv`, the dictionary itself
+    Csv,
+    /// `lemma.en.txt`
+    Lemma,
+    /// `resemble.txt`
+    Resemble,
+    /// `wordroot.txt`
+    Wordroot,
+}
+
+impl Dataset {
+    /// Tell the dataset by file name.
+    pub fn detect(path: &Path) -> Option<Dataset> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".csv") {
+            Some(Dataset::Csv)
+        } else if name.starts_with("lemma") {
+            Some(Dataset::Lemma)
+        } else if name.starts_with("resemble") {
+            Some(Dataset::Resemble)
+        } else if name.starts_with("wordroot") {
+            Some(Dataset::Wordroot)
+        } else {
+            None
+        }
+    }
+}
+
+/// Import `path` into the default database, the dataset is told by file name if `None`.
+/// The FST index is rebuilt afterwards with `index` or when it's the engine in use.
+pub async fn import(
+    path: &Path,
+    dataset: Option<Dataset>,
+    force: bool,
+    index: bool,
+) -> Result<(), Error> {
+    let dataset = dataset.or_else(|| Dataset::detect(path)).ok_or_else(|| {
+        Error::ConfigError(format!(
+            "Unknown dataset {}, pick one with --dataset",
+            path.display()
+        ))
+    })?;
+    let db = connect_db_writable().await?;
+    match dataset {
+        Dataset::Csv => {
+            let imported = import_csv(&db, path, force).await?;
+            if let Some(count) = imported {
+                println!("Imported {} records into ecdict database.", count);
+            }
+            // lookups don't build the spelling index, make sure there's one
+            let missing = suggest::index_path().is_some_and(|p| !p.exists());
+            if imported.is_some() || missing {
+                if let Some(count) = suggest::rebuild(&db).await? {
+                    println!("Built spelling index of {} words.", count);
+                }
+            }
+        }
+        dataset => {
+            if let Some(count) = import_companion(&db, path, dataset, force).await? {
+                println!("Imported {} records from {}.", count, path.display());
+            }
+        }
+    }
+    if index || Engine::from_env()? == Engine::Fst {
+        if let Some(dir) = fst_index::index_dir() {
+            let count = fst_index::build(&db, &dir).await?;
+            println!("Indexed {} records into {}.", count, dir.display());
+        }
+    }
+    Ok(())
+}
+
+/// Replace a companion dataset's tables with `path`, `None` if the file was imported before
+/// and `force` is off.
+pub async fn import_companion(
+    db: &DatabaseConnection,
+    path: &Path,
+    dataset: Dataset,
+    force: bool,
+) -> Result<Option<u64>, Error> {
+    let checksum = sha256::try_digest(path)?;
+    let imported = ecdict_imports::Entity::find()
+        .filter(ecdict_imports::Column::Checksum.eq(&checksum))
+        .filter(ecdict_imports::Column::Finished.eq(true))
+        .one(db)
+        .await?;
+    if imported.is_some() && !force {
+        println!(
+            "{} is unchanged since the last import, skipped. Use --force to import it again.",
+            path.display()
+        );
+        return Ok(None);
+    }
+
+    let count = match dataset {
+        Dataset::Lemma => companion::import_lemmas(db, path).await?,
+        Dataset::Resemble => companion::import_resemble(db, path).await?,
+        Dataset::Wordroot => companion::import_wordroots(db, path).await?,
+        Dataset::Csv => return import_csv(db, path, force).await,
+    };
+    ecdict_imports::ActiveModel {
+        path: Set(path.display().to_string()),
+        checksum: Set(checksum),
+        rows: Set(count as i32),
+        finished: Set(true),
+        updated_at: Set(chrono::Local::now().to_rfc3339()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(Some(count))
+}
+
+/// Upsert the records of `csv`, `None` if the file was imported before and `force` is off.
+pub async fn import_csv(
+    db: &DatabaseConnection,
+    csv: &Path,
+    force: bool,
+) -> Result<Option<u64>, Error> {
+    let checksum = sha256::try_digest(csv)?;
+    let last = ecdict_imports::Entity::find()
+        .filter(ecdict_imports::Column::Checksum.eq(&checksum))
+        .order_by_desc(ecdict_imports::Column::Id)
+        .one(db)
+        .await?;
+
+    let record = match last {
+        Some(last) if last.finished && !force => {
+            println!(
+                "{} is unchanged since the last import, skipped. Use --force to import it again.",
+                csv.display()
+            );
+            return Ok(None);
+        }
+        Some(last) if !last.finished => {
+            println!(
+                "Resume importing {} from record {}.",
+                csv.display(),
+                last.rows
+            );
+            last
+        }
+        _ => {
+            ecdict_imports::ActiveModel {
+                path: Set(csv.display().to_string()),
+                checksum: Set(checksum),
+                rows: Set(0),
+                finished: Set(false),
+                updated_at: Set(chrono::Local::now().to_rfc3339()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?
+        }
+    };
+
+    let size = std::fs::metadata(csv)?.len().max(1);
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_path(csv)?;
+    let skip = record.rows as u64;
+    let mut rows = skip;
+    let mut batch: Vec<words::ActiveModel> = Vec::with_capacity(BATCH_SIZE);
+    let mut records = reader.deserialize::<CsvRecord>();
+    let mut idx = 0u64;
+    while let Some(result) = records.next() {
+        idx += 1;
+        if idx <= skip {
+            continue;
+        }
+        batch.push(result?.into());
+        if batch.len() >= BATCH_SIZE {
+            rows += upsert_batch(db, &mut batch, record.id, rows).await?;
+            progress(records.reader().position().byte(), size, rows);
+        }
+    }
+    if !batch.is_empty() {
+        rows += upsert_batch(db, &mut batch, record.id, rows).await?;
+    }
+    progress(size, size, rows);
+    eprintln!();
+
+    let mut record = record.into_active_model();
+    record.rows = Set(rows as i32);
+    record.finished = Set(true);
+    record.updated_at = Set(chrono::Local::now().to_rfc3339());
+    record.update(db).await?;
+
+    let forms = lemma::index_forms(db).await?;
+    println!("Indexed {} inflected forms.", forms);
+    Ok(Some(rows - skip))
+}
+
+/// Upsert `batch` and move the import's resume point past it, in one transaction.
+async fn upsert_batch(
+    db: &DatabaseConnection,
+    batch: &mut Vec<words::ActiveModel>,
+    import_id: i32,
+    rows: u64,
+) -> Result<u64, Error> {
+    let len = batch.len() as u64;
+    let txn = db.begin().await?;
+    words::Entity::insert_many(batch.drain(..))
+        .on_conflict(
+            OnConflict::column(words::Column::Word)
+                .update_columns([
+                    words::Column::Phonetic,
+                    words::Column::Definition,
+                    words::Column::Translation,
+                    words::Column::Pos,
+                    words::Column::Collins,
+                    words::Column::Oxford,
+                    words::Column::Tag,
+                    words::Column::Bnc,
+                    words::Column::Frq,
+                    words::Column::Exchange,
+                    words::Column::Detail,
+                    words::Column::Audio,
+                    words::Column::Sw,
+                ])
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+    ecdict_imports::ActiveModel {
+        id: Set(import_id),
+        rows: Set((rows + len) as i32),
+        updated_at: Set(chrono::Local::now().to_rfc3339()),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+    txn.commit().await?;
+    Ok(len)
+}
+
+fn progress(read: u64, size: u64, rows: u64) {
+    eprint!(
+        "\rImporting: {:>5.1}% ({} records)",
+        read.min(size) as f64 * 100.0 / size as f64,
+        rows
+    );
+    io::stderr().flush().ok();
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database, EntityTrait, PaginatorTrait};
+
+    use super::import_csv;
+    use crate::translators::ecdict::{
+        entities::{ecdict_imports, words},
+        query,
+    };
+
+    const HEADER: &str = "word,phonetic,definition,translation,pos,collins,oxford,tag,bnc,frq,exchange,detail,audio\n";
+
+    #[tokio::test]
+    async fn test_import_csv() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let path =
+            std::env::temp_dir().join(format!("runslate_test_import_{}.csv", std::process::id()));
+        let csv =
+            format!("{HEADER}go,,,vi. 去,,,,,,,p:went,,\nwent,,,v. go的过去式,,,,,,,0:go/1:p,,\n");
+        std::fs::write(&path, &csv).unwrap();
+
+        assert_eq!(import_csv(&db, &path, false).await.unwrap(), Some(2));
+        // unchanged file is skipped
+        assert_eq!(import_csv(&db, &path, false).await.unwrap(), None);
+
+        // changed rows are updated in place
+        std::fs::write(&path, csv.replace("vi. 去", "vi. 去；走")).unwrap();
+        assert_eq!(import_csv(&db, &path, false).await.unwrap(), Some(2));
+        assert_eq!(words::Entity::find().count(&db).await.unwrap(), 2);
+        let go = query(&db, "go").await.unwrap().unwrap();
+        assert_eq!(go.translation.as_deref(), Some("vi. 去；走"));
+
+        // an interrupted import resumes after its committed records
+        std::fs::write(&path, format!("{csv}mouse,,,n. 老鼠,,,,,,,s:mice,,\n")).unwrap();
+        ecdict_imports::ActiveModel {
+            path: Set(path.display().to_string()),
+            checksum: Set(sha256::try_digest(&path).unwrap()),
+            rows: Set(2),
+            finished: Set(false),
+            updated_at: Set(String::new()),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        assert_eq!(import_csv(&db, &path, false).await.unwrap(), Some(1));
+        assert_eq!(words::Entity::find().count(&db).await.unwrap(), 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+//! Lemmatization through ECDICT's `exchange` field, e.g. `p:went/d:gone/i:going/3:goes`.
+//!
+//! Lemma rows list their inflections, inflected rows point back with `0:go/1:p`.
+//! [index_forms] inverts both into the `word_forms` table so any form finds its lemma.
+
+use std::collections::BTreeMap;
+
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
+};
+
+use serde::Serialize;
+
+use super::entities::{word_forms, words};
+use crate::translators::Lang;
+
+/// Exchange types of inflections, `0` (lemma) and `1` (types of the lemma) excluded.
+const FORM_KINDS: &str = "pdi3srt";
+
+/// Split `exchange` into `(type, word)` pairs.
+pub fn parse_exchange(exchange: &str) -> Vec<(&str, &str)> {
+    exchange
+        .split('/')
+        .filter_map(|item| item.split_once(':'))
+        .map(|(kind, word)| (kind.trim(), word.trim()))
+        .filter(|(kind, word)| !kind.is_empty() && !word.is_empty())
+        .collect()
+}
+
+/// `(form, lemma, kind)` relations declared by the row of `word`.
+pub fn forms_of(word: &str, exchange: &str) -> Vec<(String, String, String)> {
+    let items = parse_exchange(exchange);
+    let mut forms: Vec<(String, String, String)> = vec![];
+    let mut push = |form: &str, lemma: &str, kind: &str| {
+        if form == lemma {
+            return;
+        }
+        match forms.iter_mut().find(|(f, l, _)| f == form && l == lemma) {
+            Some((_, _, kinds)) => merge_kinds(kinds, kind),
+            None => forms.push((form.to_string(), lemma.to_string(), kind.to_string())),
+        }
+    };
+
+    for (kind, form) in &items {
+        if FORM_KINDS.contains(*kind) {
+            push(form, word, kind);
+        }
+    }
+    if let Some((_, lemma)) = items.iter().find(|(kind, _)| *kind == "0") {
+        let kind = items
+            .iter()
+            .find(|(kind, _)| *kind == "1")
+            .map(|(_, kinds)| *kinds)
+            .unwrap_or("");
+        push(word, lemma, kind);
+    }
+    forms
+}
+
+/// Append the type letters of `kind` missing from `kinds`.
+fn merge_kinds(kinds: &mut String, kind: &str) {
+    for c in kind.chars() {
+        if !kinds.contains(c) {
+            kinds.push(c);
+        }
+    }
+}
+
+/// Rebuild the `word_forms` table from `words.exchange`, returns the number of relations.
+pub async fn index_forms(db: &DatabaseConnection) -> Result<u32, DbErr> {
+    let rows = words::Entity::find()
+        .select_only()
+        .column(words::Column::Word)
+        .column(words::Column::Exchange)
+        .filter(words::Column::Exchange.is_not_null())
+        .filter(words::Column::Exchange.ne(""))
+        .into_tuple::<(String, String)>()
+        .all(db)
+        .await?;
+
+    // the same relation is usually declared on both sides
+    let mut forms: BTreeMap<(String, String), String> = BTreeMap::new();
+    for (word, exchange) in rows {
+        for (form, lemma, kind) in forms_of(&word, &exchange) {
+            merge_kinds(forms.entry((form, lemma)).or_default(), &kind);
+        }
+    }
+
+    let count = forms.len() as u32;
+    let txn = db.begin().await?;
+    word_forms::Entity::delete_many().exec(&txn).await?;
+    let models = forms
+        .into_iter()
+        .map(|((form, lemma), kind)| word_forms::ActiveModel {
+            form: Set(form),
+            lemma: Set(lemma),
+            kind: Set(kind),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for chunk in models.chunks(1000) {
+        word_forms::Entity::insert_many(chunk.to_vec())
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+    Ok(count)
+}
+
+/// `(lemma, kind)` pairs of the inflected form `word`.
+pub async fn lemmas(db: &DatabaseConnection, word: &str) -> Result<Vec<(String, String)>, DbErr> {
+    Ok(word_forms::Entity::find()
+        .filter(word_forms::Column::Form.eq(word))
+        .order_by_asc(word_forms::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|f| (f.lemma, f.kind))
+        .collect())
+}
+
+/// An inflection of a lemma, e.g. `{"kind": "p", "name": "past", "label": "过去式", "word": "went"}`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Inflection {
+    /// Exchange type letter.
+    pub kind: String,
+    /// Stable name of the type.
+    pub name: &'static str,
+    /// Name of the type in the target language.
+    pub label: &'static str,
+    pub word: String,
+}
+
+/// Inflections listed in `exchange`, labeled in `lang`.
+pub fn inflections(exchange: &str, lang: &Lang) -> Vec<Inflection> {
+    parse_exchange(exchange)
+        .into_iter()
+        .filter_map(|(kind, word)| {
+            let c = kind.chars().next().filter(|_| kind.len() == 1)?;
+            Some(Inflection {
+                kind: kind.to_string(),
+                name: kind_name(c)?,
+                label: kind_label(c, lang)?,
+                word: word.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Stable name of an exchange type letter, `None` for `0`/`1` and unknown ones.
+pub fn kind_name(kind: char) -> Option<&'static str> {
+    match kind {
+        'p' => Some("past"),
+        'd' => Some("past_participle"),
+        'i' => Some("present_participle"),
+        '3' => Some("third_person"),
+        's' => Some("plural"),
+        'r' => Some("comparative"),
+        't' => Some("superlative"),
+        _ => None,
+    }
+}
+
+/// Label of an exchange type letter, Chinese for Chinese targets, English otherwise.
+pub fn kind_label(kind: char, lang: &Lang) -> Option<&'static str> {
+    let chinese = matches!(lang, Lang::Zh | Lang::Zht | Lang::Yue);
+    let (en, zh) = match kind {
+        'p' => ("past", "过去式"),
+        'd' => ("past participle", "过去分词"),
+        'i' => ("-ing", "现在分词"),
+        '3' => ("3rd person", "第三人称单数"),
+        's' => ("plural", "复数"),
+        'r' => ("comparative", "比较级"),
+        't' => ("superlative", "最高级"),
+        _ => return None,
+    };
+    Some(if chinese { zh } else { en })
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+
+    use super::{forms_of, index_forms, inflections, lemmas, parse_exchange, Inflection};
+    use crate::translators::{ecdict::entities::words::ActiveModel, Lang};
+
+    #[test]
+    fn test_forms_of() {
+        assert_eq!(
+            parse_exchange("p:went/d:gone/"),
+            vec![("p", "went"), ("d", "gone")]
+        );
+        let forms = forms_of("persist", "d:persisted/p:persisted/i:persisting");
+        assert_eq!(
+            forms,
+            vec![
+                ("persisted".into(), "persist".into(), "dp".into()),
+                ("persisting".into(), "persist".into(), "i".into()),
+            ]
+        );
+        assert_eq!(
+            forms_of("went", "0:go/1:p"),
+            vec![("went".into(), "go".into(), "p".into())]
+        );
+    }
+
+    #[test]
+    fn test_inflections() {
+        let went = Inflection {
+            kind: "p".to_string(),
+            name: "past",
+            label: "past",
+            word: "went".to_string(),
+        };
+        assert_eq!(inflections("p:went/0:go/1:p", &Lang::En), vec![went]);
+        let labels: Vec<&str> = inflections("p:went/i:going", &Lang::Zh)
+            .into_iter()
+            .map(|i| i.label)
+            .collect();
+        assert_eq!(labels, vec!["过去式", "现在分词"]);
+    }
+
+    #[tokio::test]
+    async fn test_index_forms() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (word, exchange) in [
+            ("go", "p:went/d:gone/i:going/3:goes"),
+            ("went", "0:go/1:p"),
+            ("good", "r:better/t:best"),
+            ("better", "0:good/1:r"),
+        ] {
+            ActiveModel {
+                word: Set(word.to_string()),
+                exchange: Set(Some(exchange.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(index_forms(&db).await.unwrap(), 6);
+        assert_eq!(
+            lemmas(&db, "went").await.unwrap(),
+            vec![("go".to_string(), "p".to_string())]
+        );
+        assert_eq!(
+            lemmas(&db, "better").await.unwrap(),
+            vec![("good".to_string(), "r".to_string())]
+        );
+        assert!(lemmas(&db, "go").await.unwrap().is_empty());
+    }
+}
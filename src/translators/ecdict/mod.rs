@@ -1,66 +1,821 @@
-use sea_orm::{ColumnTrait, Database, DatabaseConnection};
-use serde_json::Value;
+use std::{collections::HashMap, path::PathBuf};
+
+use clap::ValueEnum;
+use log::warn;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ColumnTrait, Database, DatabaseConnection, DbBackend, DbErr, Statement};
+use serde_json::{json, Value};
 
 use crate::{
     errors::Error,
     translators::{Lang, Translator},
-    utils::dyer::{Colors, Dye},
+    utils::{
+        dyer::{Colors, Dye},
+        env_loader,
+    },
 };
 
+pub mod companion;
 pub mod entities;
+pub mod fst_index;
+pub mod import;
+pub mod lemma;
+pub mod random;
+pub mod search;
+pub mod suggest;
+
+/// Database file under the XDG data directory, e.g. `~/.local/share/runslate/dictionary.db`.
+const DATABASE_FILE: &str = "runslate/dictionary.db";
+
+/// Command importing ECDICT, for the hints of errors.
+const IMPORT_COMMAND: &str = "runslate ecdict import stardict.csv";
 
-pub struct Ecdict;
+/// Max headwords returned by a reverse lookup.
+const REVERSE_LIMIT: u64 = 20;
+
+/// Longest phrase tried by segmentation, in words.
+const MAX_PHRASE_WORDS: usize = 12;
+
+/// Max "did you mean" candidates shown on a miss.
+const SUGGEST_LIMIT: usize = 5;
+
+/// Where single words and phrases are looked up, `RUNSLATE_ECDICT_ENGINE`.
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum Engine {
+    /// The database, always up to date
+    Sqlite,
+    /// The compiled index built by `runslate ecdict index`, SQLite is used if it's stale
+    Fst,
+}
+
+impl Engine {
+    pub fn from_env() -> Result<Self, Error> {
+        let engine = env_loader::load_or_default("RUNSLATE_ECDICT_ENGINE", "sqlite");
+        Engine::from_str(&engine, true)
+            .map_err(|e| Error::ConfigError(format!("RUNSLATE_ECDICT_ENGINE `{engine}`: {e}")))
+    }
+}
+
+pub struct Ecdict {
+    /// Find headwords by Chinese meaning or English definition instead of by word.
+    pub reverse: bool,
+}
 
 #[async_trait::async_trait]
 impl Translator for Ecdict {
-    async fn translate(&self, words: &str, _source: &Lang, _target: &Lang) -> Result<Value, Error> {
+    async fn translate(&self, words: &str, _source: &Lang, target: &Lang) -> Result<Value, Error> {
         use sea_orm::EntityTrait;
         use sea_orm::QueryFilter;
-        let db = connect_db().await;
-        let word = words.split(' ').next().unwrap_or("");
-        let result = entities::words::Entity::find()
-            .filter(entities::words::Column::Word.eq(word))
-            .one(&db)
-            .await
-            .unwrap();
-        Ok(serde_json::to_value(result)?)
+        // nothing but a reverse lookup makes sense for a Chinese query
+        let reverse = self.reverse || words.chars().any(is_cjk);
+        // reverse lookups search meanings, which only SQLite has
+        if !reverse && Engine::from_env()? == Engine::Fst {
+            match fst_index::FstIndex::open_fresh()? {
+                Some(index) => return index.translate(words, target),
+                None => warn!("ECDICT index is missing or stale, use SQLite instead"),
+            }
+        }
+        let db = connect_db().await?;
+        check_words(&db).await?;
+        if reverse {
+            let result = reverse_query(&db, words.trim(), REVERSE_LIMIT).await?;
+            return Ok(json!({
+                "reverse": words.trim(),
+                "words": result,
+            }));
+        }
+        let tokens: Vec<&str> = words.split_whitespace().collect();
+        if tokens.len() <= 1 {
+            return lookup_word(&db, tokens.first().copied().unwrap_or(""), target).await;
+        }
+
+        // the whole phrase, e.g. `look up to`
+        let phrase = tokens.join(" ");
+        if let Some(model) = query(&db, &phrase).await? {
+            return entry_value(model, target);
+        }
+
+        // longest known phrase at each position, single words otherwise
+        let mut known = HashMap::new();
+        // stay below SQLite's limit of bound variables
+        for candidates in phrases(&tokens).chunks(512) {
+            let found = entities::words::Entity::find()
+                .filter(entities::words::Column::Word.is_in(candidates.iter().cloned()))
+                .all(&db)
+                .await?;
+            known.extend(found.into_iter().map(|m| (m.word.clone(), m)));
+        }
+        let mut entries = vec![];
+        for segment in segment_phrases(&tokens, |p| known.contains_key(p)) {
+            let entry = match known.get(&segment) {
+                Some(model) => entry_value(model.clone(), target)?,
+                None => lookup_word(&db, &segment, target).await?,
+            };
+            entries.push(entry);
+        }
+        Ok(json!({
+            "phrase": phrase,
+            "entries": entries,
+        }))
     }
-    fn show(&self, _response: &Value, _more: bool) {
-        if let Some(Value::String(word)) = _response.get("word") {
-            println!("{}", word);
+    fn show(&self, response: &Value, more: bool) {
+        if let Some(Value::Array(words)) = response.get("words") {
+            show_reverse(words, more);
+            return;
         }
-        if let Some(Value::String(exchange)) = _response.get("exchange") {
-            println!("{}", exchange.replace("/", ", ").dye(Colors::BrightCyan));
+        if let Some(Value::Array(entries)) = response.get("entries") {
+            for (idx, entry) in entries.iter().enumerate() {
+                if idx > 0 {
+                    println!();
+                }
+                show_lookup(entry, more);
+            }
+            return;
         }
-        if let Some(Value::String(phonetic)) = _response.get("phonetic") {
-            println!("{}", phonetic.dye(Colors::BrightYellow));
+        show_lookup(response, more);
+    }
+}
+
+/// Exact row, suggestions or lemma of a single word.
+async fn lookup_word(db: &DatabaseConnection, word: &str, target: &Lang) -> Result<Value, Error> {
+    let result = query(db, word).await?;
+
+    let mut lemmas = lemma::lemmas(db, word).await?;
+    merge_lemmas(
+        &mut lemmas,
+        word,
+        result.as_ref().and_then(|m| m.exchange.as_deref()),
+        companion::lemmas(db, word).await?,
+    );
+
+    if result.is_none() && lemmas.is_empty() {
+        // look up the best spelling suggestion instead
+        let suggestions = suggest::suggest(word, SUGGEST_LIMIT)?;
+        if let Some(best) = suggestions.first() {
+            if let Some(model) = query(db, best).await? {
+                let mut value = entry_value(model, target)?;
+                value["query"] = json!(word);
+                value["suggestions"] = json!(suggestions);
+                return Ok(value);
+            }
         }
-        if let Some(Value::String(definition)) = _response.get("definition") {
-            println!(
-                "{}",
-                definition.replace("\\n", "\n").dye(Colors::BrightGreen)
-            );
+    }
+
+    let mut value = match result {
+        Some(model) => entry_value(model, target)?,
+        None if lemmas.is_empty() => Value::Null,
+        None => json!({"word": word}),
+    };
+    add_companions(db, &mut value).await?;
+    let mut entries = vec![];
+    for (lemma, kind) in lemmas {
+        if let Some(model) = query(db, &lemma).await? {
+            let mut entry = entry_value(model, target)?;
+            add_companions(db, &mut entry).await?;
+            let labels = kind
+                .chars()
+                .filter_map(|c| lemma::kind_label(c, target))
+                .collect::<Vec<&str>>();
+            entry["kind"] = json!(kind);
+            entry["kind_labels"] = json!(labels);
+            entries.push(entry);
         }
-        if let Some(Value::String(translation)) = _response.get("translation") {
-            println!("{}", translation.replace("\\n", "\n").dye(Colors::Blue));
+    }
+    if !entries.is_empty() {
+        value["lemmas"] = json!(entries);
+    }
+    Ok(value)
+}
+
+fn show_lookup(response: &Value, more: bool) {
+    if let Some(Value::Array(suggestions)) = response.get("suggestions") {
+        let query = response.get("query").and_then(Value::as_str).unwrap_or("");
+        let suggestions: Vec<&str> = suggestions.iter().filter_map(Value::as_str).collect();
+        println!(
+            "{}",
+            format!(
+                "`{}` not found, did you mean: {}",
+                query,
+                suggestions.join(", ")
+            )
+            .dye(Colors::BrightBlack)
+        );
+    }
+    show_entry(response, more);
+    if let Some(Value::Array(lemmas)) = response.get("lemmas") {
+        let form = response.get("word").and_then(Value::as_str).unwrap_or("");
+        for entry in lemmas {
+            let lemma = entry.get("word").and_then(Value::as_str).unwrap_or("");
+            let kinds = match entry.get("kind_labels") {
+                Some(Value::Array(labels)) => labels.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            let header = if kinds.is_empty() {
+                format!("{form} → {lemma}")
+            } else {
+                format!("{form} → {lemma} ({})", kinds.join(", "))
+            };
+            println!();
+            println!("{}", header.dye(Colors::BrightBlack));
+            show_entry(entry, more);
         }
     }
 }
 
-pub async fn connect_db() -> DatabaseConnection {
-    let database_url =
-        std::env::var("DATABASE_URL").unwrap_or("sqlite://dictionary.db?mode=rwc".to_string());
-    Database::connect(&database_url).await.unwrap()
+/// Add the lemmas `word`'s own `exchange` and `lemma.en.txt` know to those of `word_forms`.
+fn merge_lemmas(
+    lemmas: &mut Vec<(String, String)>,
+    word: &str,
+    exchange: Option<&str>,
+    companion: Vec<String>,
+) {
+    // databases imported before the index existed still have the row's own `0:`
+    if let Some(exchange) = exchange {
+        for (form, lemma, kind) in lemma::forms_of(word, exchange) {
+            if form == word && !lemmas.iter().any(|(l, _)| *l == lemma) {
+                lemmas.push((lemma, kind));
+            }
+        }
+    }
+    // lemma.en.txt doesn't tell the kind of inflection
+    for lemma in companion {
+        if !lemmas.iter().any(|(l, _)| *l == lemma) {
+            lemmas.push((lemma, String::new()));
+        }
+    }
 }
 
-pub async fn query(db: &DatabaseConnection, word: &str) -> Option<entities::words::Model> {
+/// Add word roots and near-synonym groups of the entry's word.
+async fn add_companions(db: &DatabaseConnection, entry: &mut Value) -> Result<(), Error> {
+    let Some(word) = entry
+        .get("word")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    else {
+        return Ok(());
+    };
+    let roots = companion::roots(db, &word).await?;
+    if !roots.is_empty() {
+        entry["roots"] = json!(roots);
+    }
+    let resemble = companion::resemble(db, &word).await?;
+    if !resemble.is_empty() {
+        entry["resemble"] = json!(resemble);
+    }
+    Ok(())
+}
+
+/// JSON of a row with its parsed inflections.
+fn entry_value(model: entities::words::Model, lang: &Lang) -> Result<Value, Error> {
+    let inflections = lemma::inflections(model.exchange.as_deref().unwrap_or(""), lang);
+    let mut value = serde_json::to_value(model)?;
+    value["inflections"] = serde_json::to_value(inflections)?;
+    Ok(value)
+}
+
+fn show_entry(entry: &Value, more: bool) {
+    if let Some(Value::String(word)) = entry.get("word") {
+        println!("{}", word);
+    }
+    if let Some(Value::Array(inflections)) = entry.get("inflections") {
+        let inflections = inflections
+            .iter()
+            .filter_map(|i| {
+                Some(format!(
+                    "{}: {}",
+                    i.get("label")?.as_str()?,
+                    i.get("word")?.as_str()?
+                ))
+            })
+            .collect::<Vec<String>>();
+        if !inflections.is_empty() {
+            println!("{}", inflections.join(" · ").dye(Colors::BrightCyan));
+        }
+    }
+    if let Some(Value::String(phonetic)) = entry.get("phonetic") {
+        println!("{}", phonetic.dye(Colors::BrightYellow));
+    }
+    if let Some(Value::String(definition)) = entry.get("definition") {
+        println!(
+            "{}",
+            definition.replace("\\n", "\n").dye(Colors::BrightGreen)
+        );
+    }
+    if let Some(Value::String(translation)) = entry.get("translation") {
+        println!("{}", translation.replace("\\n", "\n").dye(Colors::Blue));
+    }
+    if more {
+        show_learner_meta(entry);
+        show_companions(entry);
+    }
+}
+
+/// Root/affix breakdown and near-synonym discrimination groups.
+fn show_companions(entry: &Value) {
+    if let Some(Value::Array(roots)) = entry.get("roots") {
+        for root in roots {
+            let field = |key: &str| root.get(key).and_then(Value::as_str).unwrap_or("");
+            let mut line = field("root").to_string();
+            if !field("class").is_empty() {
+                line.push_str(&format!(" ({})", field("class")));
+            }
+            if !field("meaning").is_empty() {
+                line.push_str(&format!(" {}", field("meaning")));
+            }
+            if !field("origin").is_empty() {
+                line.push_str(&format!(" ← {}", field("origin")));
+            }
+            println!("{}", line.dye(Colors::Yellow));
+        }
+    }
+    if let Some(Value::Array(groups)) = entry.get("resemble") {
+        for group in groups {
+            let words = match group.get("words") {
+                Some(Value::Array(words)) => words.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            println!("{}", format!("≈ {}", words.join(", ")).dye(Colors::Green));
+            if let Some(Value::String(content)) = group.get("content") {
+                for line in content.lines() {
+                    println!("  {}", line.dye(Colors::BrightBlack));
+                }
+            }
+        }
+    }
+}
+
+/// Open the offline dictionaries database read-only for ECDICT, failing with a hint if it misses
+/// migrations, which only imports apply.
+pub async fn connect_db() -> Result<DatabaseConnection, Error> {
+    connect_db_for("ECDICT", IMPORT_COMMAND).await
+}
+
+/// Open the offline dictionaries database read-only for the dictionary `name`, hints of errors
+/// tell to import it, which also migrates the database, by `command`.
+pub async fn connect_db_for(name: &str, command: &str) -> Result<DatabaseConnection, Error> {
+    if let Some(path) = database_path().filter(|p| !p.exists()) {
+        return Err(Error::Database(format!(
+            "{} doesn't exist, import {name} with `{command}`",
+            path.display()
+        )));
+    }
+    let db = Database::connect(database_url("ro")).await?;
+    // databases imported by an older version miss the newer tables and indexes
+    if !is_migrated(&db).await {
+        return Err(Error::Database(format!(
+            "{name} database was created by an older version, migrate it by running `{command}` again"
+        )));
+    }
+    Ok(db)
+}
+
+/// Open the database for imports, creating it and applying migrations as needed.
+pub async fn connect_db_writable() -> Result<DatabaseConnection, Error> {
+    if let Some(dir) = database_path().as_ref().and_then(|p| p.parent()) {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let db = Database::connect(database_url("rwc")).await?;
+    Migrator::up(&db, None).await?;
+    Ok(db)
+}
+
+async fn is_migrated(db: &DatabaseConnection) -> bool {
+    use sea_orm::ConnectionTrait;
+
+    let Ok(rows) = db
+        .query_all_raw(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT version FROM seaql_migrations",
+        ))
+        .await
+    else {
+        return false;
+    };
+    let applied = rows
+        .into_iter()
+        .filter_map(|row| row.try_get_by_index::<String>(0).ok())
+        .collect::<Vec<String>>();
+    Migrator::migrations()
+        .iter()
+        .all(|m| applied.contains(&m.name().to_string()))
+}
+
+/// Fail with a hint to import if the `words` table is empty.
+pub async fn check_words(db: &DatabaseConnection) -> Result<(), Error> {
+    check_imported::<entities::words::Entity>(db, "ECDICT", IMPORT_COMMAND).await
+}
+
+/// Fail with a hint to import the dictionary `name` by `command` if the table of `E` is empty.
+pub async fn check_imported<E: sea_orm::EntityTrait>(
+    db: &DatabaseConnection,
+    name: &str,
+    command: &str,
+) -> Result<(), Error> {
+    if E::find().one(db).await?.is_none() {
+        return Err(Error::Database(format!(
+            "{} table is empty, import {name} with `{command}`",
+            E::default().table_name()
+        )));
+    }
+    Ok(())
+}
+
+/// `RUNSLATE_ECDICT_DB` (a file), `DATABASE_URL`, or the file under the XDG data directory.
+fn database_url(mode: &str) -> String {
+    if let Some(path) = env_value("RUNSLATE_ECDICT_DB") {
+        return format!("sqlite://{path}?mode={mode}");
+    }
+    if let Some(url) = env_value("DATABASE_URL") {
+        if url.contains(":memory:") {
+            return url;
+        }
+        let base = url.split('?').next().unwrap_or(&url);
+        return format!("{base}?mode={mode}");
+    }
+    format!("sqlite://{}?mode={mode}", default_path().display())
+}
+
+/// File path of the SQLite database, `None` for in-memory databases.
+pub fn database_path() -> Option<PathBuf> {
+    if let Some(path) = env_value("RUNSLATE_ECDICT_DB") {
+        return Some(PathBuf::from(path));
+    }
+    let Some(url) = env_value("DATABASE_URL") else {
+        return Some(default_path());
+    };
+    let path = url
+        .trim_start_matches("sqlite://")
+        .trim_start_matches("sqlite:")
+        .split('?')
+        .next()
+        .unwrap_or("");
+    if path.is_empty() || path.starts_with(':') {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// `$XDG_DATA_HOME/runslate/dictionary.db`, `~/.local/share` if `XDG_DATA_HOME` isn't set.
+fn default_path() -> PathBuf {
+    env_value("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".local/share")))
+        .unwrap_or_default()
+        .join(DATABASE_FILE)
+}
+
+fn env_value(key: &str) -> Option<String> {
+    env_loader::load(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Row of `word`, tried as is, then case-insensitively, then without punctuation and spaces.
+pub async fn query(
+    db: &DatabaseConnection,
+    word: &str,
+) -> Result<Option<entities::words::Model>, DbErr> {
     use sea_orm::EntityTrait;
     use sea_orm::QueryFilter;
 
     let result = entities::words::Entity::find()
         .filter(entities::words::Column::Word.eq(word))
         .one(db)
+        .await?;
+    if result.is_some() {
+        return Ok(result);
+    }
+
+    // "English" for "english", then "e-mail" for "email"
+    for (filter, value) in [
+        ("word = ?1 COLLATE NOCASE", word.to_string()),
+        ("sw = ?1", strip_word(word)),
+    ] {
+        if value.is_empty() {
+            continue;
+        }
+        let result = entities::words::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                format!("SELECT * FROM words WHERE {filter} ORDER BY id LIMIT 1"),
+                [value.into()],
+            ))
+            .one(db)
+            .await?;
+        if result.is_some() {
+            return Ok(result);
+        }
+    }
+    Ok(None)
+}
+
+/// Lookup key of `word`: lower case, ASCII punctuation and spaces dropped.
+pub fn strip_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| !c.is_ascii_punctuation() && !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Headwords whose translation or definition contains `meaning`,
+/// ranked by Collins stars, then by frequency rank (COCA, then BNC).
+pub async fn reverse_query(
+    db: &DatabaseConnection,
+    meaning: &str,
+    limit: u64,
+) -> Result<Vec<entities::words::Model>, DbErr> {
+    use sea_orm::EntityTrait;
+
+    // trigram index can't match less than 3 characters, scan these instead
+    let filter = if meaning.chars().count() >= 3 {
+        "words_fts MATCH ?1"
+    } else {
+        "(words_fts.translation LIKE ?1 ESCAPE '\\' OR words_fts.definition LIKE ?1 ESCAPE '\\')"
+    };
+    let pattern = if meaning.chars().count() >= 3 {
+        format!("\"{}\"", meaning.replace('"', "\"\""))
+    } else {
+        // `%` and `_` in the query are literal
+        let escaped = meaning
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("%{escaped}%")
+    };
+    let sql = format!(
+        "SELECT words.* FROM words_fts JOIN words ON words.id = words_fts.rowid
+        WHERE {filter}
+        ORDER BY COALESCE(CAST(words.collins AS INTEGER), 0) DESC,
+            COALESCE(
+                NULLIF(CAST(words.frq AS INTEGER), 0),
+                NULLIF(CAST(words.bnc AS INTEGER), 0),
+                1000000
+            ),
+            words.id
+        LIMIT ?2"
+    );
+
+    entities::words::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            sql,
+            [pattern.into(), limit.into()],
+        ))
+        .all(db)
         .await
-        .unwrap();
-    result
+}
+
+pub(crate) fn show_reverse(words: &[Value], more: bool) {
+    for word in words {
+        let Some(Value::String(headword)) = word.get("word") else {
+            continue;
+        };
+        let translation = match word.get("translation") {
+            Some(Value::String(translation)) => translation.replace("\\n", "; "),
+            _ => String::new(),
+        };
+        println!(
+            "{} {}",
+            headword.dye(Colors::BrightWhite),
+            translation.dye(Colors::Cyan)
+        );
+        if more {
+            if let Some(Value::String(definition)) = word.get("definition") {
+                let definition = definition.replace("\\n", "; ");
+                println!("  {}", definition.dye(Colors::BrightBlack));
+            }
+        }
+    }
+}
+
+/// Collins stars, Oxford 3000 marker, exam tags and frequency ranks.
+fn show_learner_meta(entry: &Value) {
+    let field = |key: &str| entry.get(key).and_then(Value::as_str).map(str::trim);
+    let mut meta = vec![];
+    if let Some(stars) = field("collins").and_then(collins_stars) {
+        meta.push(stars);
+    }
+    if field("oxford") == Some("1") {
+        meta.push("Oxford 3000".to_string());
+    }
+    let tags = tag_names(field("tag").unwrap_or(""));
+    if !tags.is_empty() {
+        meta.push(tags.join(" "));
+    }
+    for (name, key) in [("BNC", "bnc"), ("COCA", "frq")] {
+        if let Some(rank) = field(key)
+            .and_then(|r| r.parse::<u32>().ok())
+            .filter(|r| *r > 0)
+        {
+            meta.push(format!("{name} #{rank}"));
+        }
+    }
+    if !meta.is_empty() {
+        println!("{}", meta.join(" · ").dye(Colors::Magenta));
+    }
+}
+
+/// `3` -> `★★★☆☆`, `None` for words without a Collins rating.
+fn collins_stars(collins: &str) -> Option<String> {
+    let stars = collins
+        .parse::<usize>()
+        .ok()
+        .filter(|s| (1..=5).contains(s))?;
+    Some("★".repeat(stars) + &"☆".repeat(5 - stars))
+}
+
+/// Readable names of ECDICT exam tags, unknown tags are kept as is.
+fn tag_names(tag: &str) -> Vec<&str> {
+    tag.split_whitespace()
+        .map(|t| match t {
+            "zk" => "中考",
+            "gk" => "高考",
+            "cet4" => "CET-4",
+            "cet6" => "CET-6",
+            "ky" => "考研",
+            "toefl" => "TOEFL",
+            "ielts" => "IELTS",
+            "gre" => "GRE",
+            t => t,
+        })
+        .collect()
+}
+
+/// Runs of two to [MAX_PHRASE_WORDS] consecutive tokens, candidates for phrase headwords.
+fn phrases(tokens: &[&str]) -> Vec<String> {
+    let mut phrases = vec![];
+    for start in 0..tokens.len() {
+        for end in start + 2..=tokens.len().min(start + MAX_PHRASE_WORDS) {
+            phrases.push(tokens[start..end].join(" "));
+        }
+    }
+    phrases
+}
+
+/// Longest known phrase at each position, other tokens become segments of their own.
+fn segment_phrases<F>(tokens: &[&str], known: F) -> Vec<String>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut segments = vec![];
+    let mut start = 0;
+    while start < tokens.len() {
+        let end = (start + 2..=tokens.len().min(start + MAX_PHRASE_WORDS))
+            .rev()
+            .find(|&end| known(&tokens[start..end].join(" ")))
+            .unwrap_or(start + 1);
+        segments.push(tokens[start..end].join(" "));
+        start = end;
+    }
+    segments
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{
+        ActiveModelTrait, ActiveValue::Set, ConnectionTrait, Database, DbBackend, Statement,
+    };
+
+    use super::{
+        collins_stars, entities::words::ActiveModel, phrases, query, reverse_query,
+        segment_phrases, strip_word, tag_names,
+    };
+
+    #[tokio::test]
+    async fn test_query_variants() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for word in ["English", "english", "e-mail"] {
+            ActiveModel {
+                word: Set(word.to_string()),
+                sw: Set(Some(strip_word(word))),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let word = |w: &str| {
+            let db = &db;
+            let w = w.to_string();
+            async move { query(db, &w).await.unwrap().map(|m| m.word) }
+        };
+        assert_eq!(word("English").await.as_deref(), Some("English"));
+        assert_eq!(word("english").await.as_deref(), Some("english"));
+        assert_eq!(word("ENGLISH").await.as_deref(), Some("English"));
+        assert_eq!(word("email").await.as_deref(), Some("e-mail"));
+        assert_eq!(word("E mail").await.as_deref(), Some("e-mail"));
+        assert_eq!(word("mail").await, None);
+
+        let model = query(&db, "email").await.unwrap().unwrap();
+        assert_eq!(model.sw.as_deref(), Some("email"));
+        assert!(serde_json::to_value(model).unwrap().get("sw").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_indexes_migration() {
+        let insert = |word: &str| format!("INSERT INTO words (word) VALUES ('{word}')");
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        // up to the migration adding `sw`
+        Migrator::up(&db, Some(6)).await.unwrap();
+        let word = "E-mail\u{3000}Box\tit's";
+        db.execute_unprepared(&insert(&word.replace('\'', "''")))
+            .await
+            .unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        let model = query(&db, word).await.unwrap().unwrap();
+        assert_eq!(model.sw, Some(strip_word(word)));
+
+        // duplicates are left for a new import to fix, not deleted
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, Some(6)).await.unwrap();
+        for word in ["go", "go", "went"] {
+            db.execute_unprepared(&insert(word)).await.unwrap();
+        }
+        assert!(Migrator::up(&db, None).await.is_err());
+        let count = db
+            .query_one_raw(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT COUNT(*) FROM words",
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get_by_index::<i64>(0)
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_segment_phrases() {
+        let known = ["look up", "look up to", "in spite of"];
+        assert_eq!(
+            segment_phrases(
+                &["look", "up", "to", "her", "in", "spite", "of", "it"],
+                |p| known.contains(&p)
+            ),
+            vec!["look up to", "her", "in spite of", "it"]
+        );
+        assert_eq!(phrases(&["a", "b", "c"]), vec!["a b", "a b c", "b c"]);
+        // linear in the tokens, not quadratic
+        assert_eq!(phrases(&["w"; 1000]).len(), 989 * 11 + 55);
+    }
+
+    #[test]
+    fn test_learner_meta() {
+        assert_eq!(collins_stars("3").as_deref(), Some("★★★☆☆"));
+        assert_eq!(collins_stars("0"), None);
+        assert_eq!(collins_stars(""), None);
+        assert_eq!(
+            tag_names("zk gk cet4 xyz"),
+            vec!["中考", "高考", "CET-4", "xyz"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reverse_query() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (word, translation, collins, frq) in [
+            ("persist", "vi. 坚持；持续", "3", "3000"),
+            ("insist", "vi. 坚持；强调", "4", "1800"),
+            ("hold", "vt. 持有", "5", "100"),
+            ("percent", "n. 百分比(%)", "3", "900"),
+        ] {
+            ActiveModel {
+                word: Set(word.to_string()),
+                translation: Set(Some(translation.to_string())),
+                collins: Set(Some(collins.to_string())),
+                frq: Set(Some(frq.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let words: Vec<String> = reverse_query(&db, "坚持", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.word)
+            .collect();
+        assert_eq!(words, vec!["insist", "persist"]);
+
+        let words = reverse_query(&db, "坚持；持续", 10).await.unwrap();
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].word, "persist");
+
+        // short queries are scanned by LIKE, `%` isn't a wildcard there
+        let words = reverse_query(&db, "%", 10).await.unwrap();
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].word, "percent");
+        assert!(reverse_query(&db, "_", 10).await.unwrap().is_empty());
+    }
 }
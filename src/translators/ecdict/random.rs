@@ -0,0 +1,439 @@
+//! `runslate ecdict random` and `runslate wotd`: words drawn from ECDICT by tag, level and frequency.
+//!
+//! The word of the day is picked by a seed derived from the date, days already shown are kept in
+//! `<db>.wotd.json` next to the database so the word sticks for the day, as long as it matches the
+//! filters, and doesn't come back within `--window` days.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use chrono::{Local, NaiveDate};
+use clap::Args;
+use log::{debug, warn};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, Statement, Value as DbValue,
+};
+
+use crate::{
+    errors::Error,
+    translators::Lang,
+    utils::dyer::{Colors, Dye},
+};
+
+use super::{
+    check_words, connect_db, database_path, entities::words, entry_value, search::WordFilter,
+    show_entry,
+};
+
+/// Seeds tried before giving up on avoiding recent words.
+const WOTD_TRIES: usize = 64;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Args)]
+pub struct RandomArgs {
+    #[command(flatten)]
+    pub filter: WordFilter,
+
+    /// [number] Number of words
+    #[arg(short = 'n', long, default_value = "1")]
+    pub count: u64,
+
+    /// [bool] Print `word [phonetic] translation` on one plain line, for MOTDs and status bars
+    #[arg(short = '1', long)]
+    pub oneline: bool,
+
+    /// [bool] Show definitions and learner metadata too
+    #[arg(short, long)]
+    pub more: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct WotdArgs {
+    #[command(flatten)]
+    pub filter: WordFilter,
+
+    /// [date] Day to pick the word for, e.g. `2026-10-18`, today by default
+    #[arg(long)]
+    pub date: Option<NaiveDate>,
+
+    /// [number] Days within which a word isn't picked again
+    #[arg(short, long, default_value = "365", env = "RUNSLATE_WOTD_WINDOW")]
+    pub window: u32,
+
+    /// [bool] Print `word [phonetic] translation` on one plain line, for MOTDs and status bars
+    #[arg(short = '1', long)]
+    pub oneline: bool,
+
+    /// [bool] Show definitions and learner metadata too
+    #[arg(short, long)]
+    pub more: bool,
+
+    /// [bool] Print debug details
+    #[arg(short = 'v', long, env = "RUNSLATE_VERBOSE")]
+    pub verbose: bool,
+}
+
+/// Print random words matching the filters.
+pub async fn random(args: &RandomArgs) -> Result<(), Error> {
+    let db = connect_db().await?;
+    check_words(&db).await?;
+    let words = random_words(&db, &args.filter, args.count.max(1)).await?;
+    if words.is_empty() {
+        println!("{}", "No word matches the filters".dye(Colors::BrightBlack));
+    }
+    for (i, model) in words.into_iter().enumerate() {
+        if !args.oneline && i > 0 {
+            println!();
+        }
+        show_word(model, args.oneline, args.more)?;
+    }
+    Ok(())
+}
+
+/// Print the word of the day, the same one all day long.
+pub async fn wotd(args: &WotdArgs) -> Result<(), Error> {
+    let db = connect_db().await?;
+    check_words(&db).await?;
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+    let mut history = load_history();
+
+    let key = date.format(DATE_FORMAT).to_string();
+    let shown = history.get(&key).cloned();
+    let Some(model) = day_word(&db, &args.filter, date, args.window, &mut history).await? else {
+        println!("{}", "No word matches the filters".dye(Colors::BrightBlack));
+        return Ok(());
+    };
+    if history.get(&key) != shown.as_ref() {
+        save_history(&history, date, args.window);
+    }
+
+    if !args.oneline {
+        println!(
+            "{}",
+            format!("Word of the day, {}", date.format(DATE_FORMAT)).dye(Colors::BrightBlack)
+        );
+    }
+    show_word(model, args.oneline, args.more)
+}
+
+/// Word of the day for `date`: the one in `history` if it matches the filters, otherwise a new
+/// pick which replaces it in `history`.
+pub async fn day_word(
+    db: &DatabaseConnection,
+    filter: &WordFilter,
+    date: NaiveDate,
+    window: u32,
+    history: &mut BTreeMap<String, String>,
+) -> Result<Option<words::Model>, DbErr> {
+    let key = date.format(DATE_FORMAT).to_string();
+    if let Some(word) = history.get(&key) {
+        if let Some(model) = matching_word(db, filter, word).await? {
+            return Ok(Some(model));
+        }
+        debug!("{word} shown on {key} doesn't match the filters");
+    }
+    let recent = recent_words(history, date, window);
+    let model = word_of_the_day(db, filter, date, &recent).await?;
+    if let Some(model) = &model {
+        history.insert(key, model.word.clone());
+    }
+    Ok(model)
+}
+
+/// `word` if it's among the candidates of the filters.
+async fn matching_word(
+    db: &DatabaseConnection,
+    filter: &WordFilter,
+    word: &str,
+) -> Result<Option<words::Model>, DbErr> {
+    let (condition, mut values) = candidates(filter);
+    values.push(word.into());
+    words::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            format!("SELECT * FROM words WHERE {condition} AND word = ? LIMIT 1"),
+            values,
+        ))
+        .one(db)
+        .await
+}
+
+/// `count` random single words with a translation.
+pub async fn random_words(
+    db: &DatabaseConnection,
+    filter: &WordFilter,
+    count: u64,
+) -> Result<Vec<words::Model>, DbErr> {
+    let (condition, mut values) = candidates(filter);
+    let sql = format!("SELECT * FROM words WHERE {condition} ORDER BY RANDOM() LIMIT ?");
+    debug!("{sql}");
+    values.push(count.into());
+    words::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            sql,
+            values,
+        ))
+        .all(db)
+        .await
+}
+
+/// Word picked for `date`, skipping `recent` ones unless nothing else is found.
+pub async fn word_of_the_day(
+    db: &DatabaseConnection,
+    filter: &WordFilter,
+    date: NaiveDate,
+    recent: &[String],
+) -> Result<Option<words::Model>, DbErr> {
+    let (condition, values) = candidates(filter);
+    let total = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            format!("SELECT COUNT(*) FROM words WHERE {condition}"),
+            values.clone(),
+        ))
+        .await?
+        .map(|row| row.try_get_by_index::<i64>(0))
+        .transpose()?
+        .unwrap_or(0) as u64;
+    if total == 0 {
+        return Ok(None);
+    }
+
+    let sql = format!("SELECT * FROM words WHERE {condition} ORDER BY id LIMIT 1 OFFSET ?");
+    let mut seed = date_seed(date);
+    let mut picked = None;
+    for _ in 0..WOTD_TRIES {
+        let offset = splitmix64(&mut seed) % total;
+        let mut values = values.clone();
+        values.push(offset.into());
+        let model = words::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                sql.clone(),
+                values,
+            ))
+            .one(db)
+            .await?;
+        match model {
+            Some(model) if recent.contains(&model.word) => picked = picked.or(Some(model)),
+            model => return Ok(model.or(picked)),
+        }
+    }
+    Ok(picked)
+}
+
+/// Conditions of the filters, limited to single words which have a translation.
+fn candidates(filter: &WordFilter) -> (String, Vec<DbValue>) {
+    let (mut conditions, values) = filter.conditions();
+    conditions.push("word NOT LIKE '% %'".to_string());
+    conditions.push("COALESCE(translation, '') != ''".to_string());
+    (conditions.join(" AND "), values)
+}
+
+fn show_word(model: words::Model, oneline: bool, more: bool) -> Result<(), Error> {
+    if oneline {
+        println!("{}", oneline_text(&model));
+        return Ok(());
+    }
+    show_entry(&entry_value(model, &Lang::Zh)?, more);
+    Ok(())
+}
+
+/// `word [phonetic] translation`, without colors.
+fn oneline_text(model: &words::Model) -> String {
+    let mut text = model.word.clone();
+    if let Some(phonetic) = model.phonetic.as_deref().filter(|p| !p.is_empty()) {
+        text.push_str(&format!(" [{phonetic}]"));
+    }
+    if let Some(translation) = model.translation.as_deref().filter(|t| !t.is_empty()) {
+        text.push(' ');
+        text.push_str(&translation.replace("\\n", "; "));
+    }
+    text
+}
+
+/// Stable across runs and platforms, unlike `DefaultHasher`.
+fn date_seed(date: NaiveDate) -> u64 {
+    let digest = sha256::digest(date.format(DATE_FORMAT).to_string());
+    u64::from_str_radix(&digest[..16], 16).unwrap_or_default()
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Words shown within `window` days of `date`.
+fn recent_words(history: &BTreeMap<String, String>, date: NaiveDate, window: u32) -> Vec<String> {
+    history
+        .iter()
+        .filter(|(day, _)| {
+            NaiveDate::parse_from_str(day, DATE_FORMAT)
+                .map(|day| (day - date).num_days().unsigned_abs() < window as u64)
+                .unwrap_or(false)
+        })
+        .map(|(_, word)| word.clone())
+        .collect()
+}
+
+fn history_path() -> Option<PathBuf> {
+    database_path().map(|p| p.with_extension("wotd.json"))
+}
+
+/// Shown words by date, empty if there's no history yet.
+fn load_history() -> BTreeMap<String, String> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Save the history, dropping days out of the window of `date`.
+fn save_history(history: &BTreeMap<String, String>, date: NaiveDate, window: u32) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    let recent = history
+        .iter()
+        .filter(|(day, _)| {
+            NaiveDate::parse_from_str(day, DATE_FORMAT)
+                .map(|day| (date - day).num_days() < window as i64)
+                .unwrap_or(false)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let saved = serde_json::to_string_pretty(&recent)
+        .map_err(Error::from)
+        .and_then(|text| Ok(fs::write(&path, text)?));
+    if let Err(e) = saved {
+        warn!(
+            "Save word of the day history to {} failed: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use chrono::NaiveDate;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database, DatabaseConnection};
+
+    use super::{day_word, random_words, recent_words, word_of_the_day};
+    use crate::translators::ecdict::{entities::words::ActiveModel, search::WordFilter};
+
+    async fn day(
+        db: &DatabaseConnection,
+        filter: &WordFilter,
+        date: NaiveDate,
+        history: &mut BTreeMap<String, String>,
+    ) -> Option<String> {
+        day_word(db, filter, date, 30, history)
+            .await
+            .unwrap()
+            .map(|m| m.word)
+    }
+
+    #[tokio::test]
+    async fn test_word_of_the_day() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (word, tag, translation) in [
+            ("abandon", "cet4", "v. 放弃"),
+            ("ability", "cet4", "n. 能力"),
+            ("abolish", "cet6", "v. 废除"),
+            ("absurd", "cet6", "a. 荒谬的"),
+            ("look up", "cet4", "查找"),
+            ("zzz", "cet4", ""),
+        ] {
+            ActiveModel {
+                word: Set(word.to_string()),
+                tag: Set(Some(tag.to_string())),
+                translation: Set(Some(translation.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let cet4 = WordFilter {
+            tag: vec!["cet4".to_string()],
+            ..Default::default()
+        };
+        let mut words = random_words(&db, &cet4, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.word)
+            .collect::<Vec<_>>();
+        words.sort();
+        assert_eq!(words, vec!["abandon", "ability"]);
+
+        let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let pick = |recent: Vec<String>| {
+            let (db, cet4) = (&db, &cet4);
+            async move {
+                word_of_the_day(db, cet4, date, &recent)
+                    .await
+                    .unwrap()
+                    .map(|m| m.word)
+            }
+        };
+        let word = pick(vec![]).await.unwrap();
+        assert_eq!(pick(vec![]).await.unwrap(), word);
+        let other = pick(vec![word.clone()]).await.unwrap();
+        assert_ne!(other, word);
+        // everything was shown recently, repeat rather than show nothing
+        assert!(pick(vec!["abandon".into(), "ability".into()])
+            .await
+            .is_some());
+
+        let history = BTreeMap::from([
+            ("2026-10-10".to_string(), "abandon".to_string()),
+            ("2025-10-10".to_string(), "ability".to_string()),
+        ]);
+        assert_eq!(recent_words(&history, date, 30), vec!["abandon"]);
+
+        // the day's word sticks while it matches the filters
+        let mut history = BTreeMap::new();
+        let word = day(&db, &cet4, date, &mut history).await.unwrap();
+        assert_eq!(history.get("2026-10-18"), Some(&word));
+        assert_eq!(day(&db, &cet4, date, &mut history).await.unwrap(), word);
+        assert_eq!(
+            day(&db, &WordFilter::default(), date, &mut history)
+                .await
+                .unwrap(),
+            word
+        );
+
+        // but not once the filters exclude it
+        let cet6 = WordFilter {
+            tag: vec!["cet6".to_string()],
+            ..Default::default()
+        };
+        let other = day(&db, &cet6, date, &mut history).await.unwrap();
+        assert!(["abolish", "absurd"].contains(&other.as_str()));
+        assert_eq!(history.get("2026-10-18"), Some(&other));
+
+        // words shown within the window aren't picked again, older ones may be
+        let mut history = BTreeMap::from([("2026-10-10".to_string(), "abolish".to_string())]);
+        assert_eq!(day(&db, &cet6, date, &mut history).await.unwrap(), "absurd");
+        let mut history = BTreeMap::from([("2026-08-10".to_string(), "abolish".to_string())]);
+        let word = day(&db, &cet6, date, &mut history).await.unwrap();
+        assert_eq!(
+            word_of_the_day(&db, &cet6, date, &[])
+                .await
+                .unwrap()
+                .unwrap()
+                .word,
+            word
+        );
+    }
+}
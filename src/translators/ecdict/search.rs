@@ -0,0 +1,398 @@
+//! `runslate ecdict search`: pattern, tag and frequency filters over ECDICT headwords.
+
+use clap::{Args, ValueEnum};
+use log::debug;
+use regex::Regex;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    Statement, Value as DbValue,
+};
+
+use crate::{
+    errors::Error,
+    utils::dyer::{Colors, Dye},
+};
+
+use super::{check_words, connect_db, entities::words, show_reverse};
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// [string] Glob (`inter*`, `c?t`), or regex when anchored with `^`/`$` (`^un.*able$`)
+    pub pattern: Option<String>,
+
+    /// [bool] Treat the pattern as a regex
+    #[arg(short = 'E', long)]
+    pub regex: bool,
+
+    #[command(flatten)]
+    pub filter: WordFilter,
+
+    /// [enum] Sort by
+    #[arg(short, long, value_enum, default_value = "word")]
+    pub sort: SearchSort,
+
+    /// [number] Words per page
+    #[arg(short = 'n', long, default_value = "50")]
+    pub limit: u64,
+
+    /// [number] Page to show, starts at 1
+    #[arg(short, long, default_value = "1")]
+    pub page: u64,
+
+    /// [bool] Show definitions too
+    #[arg(short, long)]
+    pub more: bool,
+}
+
+/// Filters on ECDICT's metadata, shared by `search`, `random` and `wotd`.
+#[derive(Debug, Default, Args)]
+pub struct WordFilter {
+    /// [range] Word length, e.g. `5`, `3..6`, `8..`
+    #[arg(short, long, value_parser = parse_range)]
+    pub length: Option<Range>,
+
+    /// [strings] Exam tags the word must have: zk gk cet4 cet6 ky toefl ielts gre
+    #[arg(long)]
+    pub tag: Vec<String>,
+
+    /// [bool] Oxford 3000 core words only
+    #[arg(long)]
+    pub oxford: bool,
+
+    /// [range] Collins stars, e.g. `3..`
+    #[arg(long, value_parser = parse_range)]
+    pub collins: Option<Range>,
+
+    /// [range] COCA frequency rank, e.g. `..5000`
+    #[arg(long, value_parser = parse_range)]
+    pub frq: Option<Range>,
+
+    /// [range] BNC frequency rank, e.g. `1000..3000`
+    #[arg(long, value_parser = parse_range)]
+    pub bnc: Option<Range>,
+}
+
+impl WordFilter {
+    /// SQL conditions on `words` and their bound values.
+    pub fn conditions(&self) -> (Vec<String>, Vec<DbValue>) {
+        let mut conditions: Vec<String> = vec![];
+        let mut values: Vec<DbValue> = vec![];
+        if let Some(length) = &self.length {
+            push_range(&mut conditions, &mut values, "LENGTH(word)", length);
+        }
+        for tag in &self.tag {
+            conditions.push("(' ' || COALESCE(tag, '') || ' ') LIKE ?".to_string());
+            values.push(format!("% {} %", tag.to_lowercase()).into());
+        }
+        if self.oxford {
+            conditions.push("oxford = '1'".to_string());
+        }
+        if let Some(collins) = &self.collins {
+            push_range(
+                &mut conditions,
+                &mut values,
+                "CAST(collins AS INTEGER)",
+                collins,
+            );
+        }
+        // rank 0 means unranked
+        if let Some(frq) = &self.frq {
+            push_range(
+                &mut conditions,
+                &mut values,
+                "NULLIF(CAST(frq AS INTEGER), 0)",
+                frq,
+            );
+        }
+        if let Some(bnc) = &self.bnc {
+            push_range(
+                &mut conditions,
+                &mut values,
+                "NULLIF(CAST(bnc AS INTEGER), 0)",
+                bnc,
+            );
+        }
+        (conditions, values)
+    }
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum SearchSort {
+    Word,
+    Length,
+    /// Collins stars, most first
+    Collins,
+    /// COCA rank, most frequent first
+    Frq,
+    /// BNC rank, most frequent first
+    Bnc,
+}
+
+/// Inclusive bounds, either side may be open.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Range {
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+/// `5`, `3..6`, `3..` or `..6`.
+pub fn parse_range(s: &str) -> Result<Range, String> {
+    let bound = |b: &str| -> Result<Option<u32>, String> {
+        match b.trim() {
+            "" => Ok(None),
+            b => b.parse::<u32>().map(Some).map_err(|e| format!("{b}: {e}")),
+        }
+    };
+    match s.split_once("..") {
+        Some((min, max)) => Ok(Range {
+            min: bound(min)?,
+            max: bound(max.trim_start_matches('='))?,
+        }),
+        None => {
+            let n = bound(s)?.ok_or("empty range")?;
+            Ok(Range {
+                min: Some(n),
+                max: Some(n),
+            })
+        }
+    }
+}
+
+/// Search and print one page of matching headwords.
+pub async fn search(args: &SearchArgs) -> Result<(), Error> {
+    let db = connect_db().await?;
+    check_words(&db).await?;
+    let (words, total) = find(&db, args).await?;
+    show_reverse(
+        &serde_json::to_value(words)?
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        args.more,
+    );
+
+    let pages = total.div_ceil(args.limit.max(1)).max(1);
+    println!(
+        "{}",
+        format!("page {}/{}, {} words", args.page, pages, total).dye(Colors::BrightBlack)
+    );
+    Ok(())
+}
+
+/// One page of matching words and the number of all matches.
+pub async fn find(
+    db: &DatabaseConnection,
+    args: &SearchArgs,
+) -> Result<(Vec<words::Model>, u64), Error> {
+    let regex = match &args.pattern {
+        Some(p) if args.regex || p.starts_with('^') || p.ends_with('$') => Some(
+            Regex::new(&format!("(?i){p}"))
+                .map_err(|e| Error::InvalidArgument(format!("Bad regex `{p}`: {e}")))?,
+        ),
+        _ => None,
+    };
+
+    let (mut conditions, mut values) = args.filter.conditions();
+    if let (Some(pattern), None) = (&args.pattern, &regex) {
+        conditions.push("word LIKE ? ESCAPE '\\'".to_string());
+        values.push(glob_to_like(pattern).into());
+    }
+
+    let filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let order = match args.sort {
+        SearchSort::Word => "word",
+        SearchSort::Length => "LENGTH(word), word",
+        SearchSort::Collins => "COALESCE(CAST(collins AS INTEGER), 0) DESC, word",
+        SearchSort::Frq => "COALESCE(NULLIF(CAST(frq AS INTEGER), 0), 1000000000), word",
+        SearchSort::Bnc => "COALESCE(NULLIF(CAST(bnc AS INTEGER), 0), 1000000000), word",
+    };
+    let limit = args.limit.max(1);
+    let offset = args.page.max(1).saturating_sub(1) * limit;
+
+    // SQLite has no REGEXP, regex patterns are matched here after the other filters,
+    // on headwords only, full rows are read for the page
+    if let Some(regex) = regex {
+        let sql = format!("SELECT id, word FROM words {filter} ORDER BY {order}");
+        debug!("{sql}");
+        let matched = db
+            .query_all_raw(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                sql,
+                values,
+            ))
+            .await?
+            .into_iter()
+            .map(|row| Ok((row.try_get_by_index::<i32>(0)?, row.try_get_by_index(1)?)))
+            .collect::<Result<Vec<(i32, String)>, DbErr>>()?
+            .into_iter()
+            .filter(|(_, word)| regex.is_match(word))
+            .map(|(id, _)| id)
+            .collect::<Vec<i32>>();
+        let total = matched.len() as u64;
+        let ids = matched
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect::<Vec<i32>>();
+        let mut rows = words::Entity::find()
+            .filter(words::Column::Id.is_in(ids.clone()))
+            .all(db)
+            .await?;
+        rows.sort_by_key(|w| ids.iter().position(|id| *id == w.id));
+        return Ok((rows, total));
+    }
+
+    let count_sql = format!("SELECT COUNT(*) FROM words {filter}");
+    let total = db
+        .query_one_raw(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            count_sql,
+            values.clone(),
+        ))
+        .await?
+        .map(|row| row.try_get_by_index::<i64>(0))
+        .transpose()?
+        .unwrap_or(0) as u64;
+
+    let sql = format!("SELECT * FROM words {filter} ORDER BY {order} LIMIT ? OFFSET ?");
+    debug!("{sql}");
+    values.push(limit.into());
+    values.push(offset.into());
+    let page = words::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            sql,
+            values,
+        ))
+        .all(db)
+        .await?;
+    Ok((page, total))
+}
+
+fn push_range(conditions: &mut Vec<String>, values: &mut Vec<DbValue>, expr: &str, range: &Range) {
+    if let Some(min) = range.min {
+        conditions.push(format!("{expr} >= ?"));
+        values.push(min.into());
+    }
+    if let Some(max) = range.max {
+        conditions.push(format!("{expr} <= ?"));
+        values.push(max.into());
+    }
+}
+
+/// `inter*` -> `inter%`, `c?t` -> `c_t`, with `%`, `_` and `\` escaped.
+fn glob_to_like(glob: &str) -> String {
+    let mut like = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' => like.push('%'),
+            '?' => like.push('_'),
+            '%' | '_' | '\\' => {
+                like.push('\\');
+                like.push(c);
+            }
+            c => like.push(c),
+        }
+    }
+    like
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+
+    use super::{find, glob_to_like, parse_range, Range, SearchArgs};
+    use crate::translators::ecdict::entities::words::ActiveModel;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        search: SearchArgs,
+    }
+
+    fn args(line: &str) -> SearchArgs {
+        Cli::parse_from(std::iter::once("search").chain(line.split_whitespace())).search
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_range("3.."),
+            Ok(Range {
+                min: Some(3),
+                max: None
+            })
+        );
+        assert_eq!(
+            parse_range("5"),
+            Ok(Range {
+                min: Some(5),
+                max: Some(5)
+            })
+        );
+        assert!(parse_range("a..b").is_err());
+        assert_eq!(glob_to_like("c?t_*"), "c_t\\_%");
+    }
+
+    #[tokio::test]
+    async fn test_find() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (word, tag, collins, frq) in [
+            ("cat", "zk gk", "5", "900"),
+            ("cut", "zk gk cet4", "5", "300"),
+            ("intern", "cet6 gre", "2", "8000"),
+            ("international", "cet4", "4", "700"),
+            ("unbelievable", "gre", "3", "0"),
+        ] {
+            ActiveModel {
+                word: Set(word.to_string()),
+                tag: Set(Some(tag.to_string())),
+                collins: Set(Some(collins.to_string())),
+                frq: Set(Some(frq.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let words = |(page, total): (Vec<super::words::Model>, u64)| {
+            (page.into_iter().map(|m| m.word).collect::<Vec<_>>(), total)
+        };
+        assert_eq!(
+            words(find(&db, &args("c?t")).await.unwrap()),
+            (vec!["cat".into(), "cut".into()], 2)
+        );
+        assert_eq!(
+            words(find(&db, &args("inter* --sort frq")).await.unwrap()),
+            (vec!["international".into(), "intern".into()], 2)
+        );
+        assert_eq!(
+            words(find(&db, &args("^un.*able$")).await.unwrap()),
+            (vec!["unbelievable".into()], 1)
+        );
+        assert_eq!(
+            words(find(&db, &args("^c.t$ -n 1 -p 2")).await.unwrap()),
+            (vec!["cut".into()], 2)
+        );
+        assert_eq!(
+            words(find(&db, &args("--tag gre --collins 3..")).await.unwrap()),
+            (vec!["unbelievable".into()], 1)
+        );
+        assert_eq!(
+            words(
+                find(&db, &args("--frq ..1000 --length 3 -n 1 -p 2"))
+                    .await
+                    .unwrap()
+            ),
+            (vec!["cut".into()], 2)
+        );
+    }
+}
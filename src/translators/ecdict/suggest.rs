@@ -0,0 +1,342 @@
+//! "Did you mean" suggestions for ECDICT misses, found with a BK-tree over all headwords.
+//!
+//! The tree is built by `ecdict import` and saved next to the database, see [index_path].
+//! Building it takes a while, so lookups don't, they ask for an import if it's missing.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use sea_orm::{DatabaseConnection, EntityTrait, QuerySelect};
+
+use crate::errors::Error;
+
+use super::{database_path, entities, IMPORT_COMMAND};
+
+const MAGIC: &[u8; 8] = b"RSBKTREE";
+
+/// A BK-tree under Levenshtein distance, nodes are kept in an arena.
+#[derive(Debug, Default)]
+pub struct BkTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    word: String,
+    /// Frequency rank, lower is more common.
+    rank: u32,
+    /// `(distance, node index)`
+    children: Vec<(u8, u32)>,
+}
+
+impl BkTree {
+    pub fn insert(&mut self, word: &str, rank: u32) {
+        let node = Node {
+            word: word.to_string(),
+            rank,
+            children: vec![],
+        };
+        if self.nodes.is_empty() {
+            self.nodes.push(node);
+            return;
+        }
+
+        let mut cur = 0usize;
+        loop {
+            let dist = levenshtein(&self.nodes[cur].word, word);
+            if dist == 0 {
+                return;
+            }
+            let dist = dist.min(u8::MAX as usize) as u8;
+            match self.nodes[cur].children.iter().find(|(d, _)| *d == dist) {
+                Some((_, idx)) => cur = *idx as usize,
+                None => {
+                    let idx = self.nodes.len() as u32;
+                    self.nodes.push(node);
+                    self.nodes[cur].children.push((dist, idx));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Words within `max` edits of `word`, closest and most frequent first.
+    pub fn find(&self, word: &str, max: usize) -> Vec<(&str, usize)> {
+        self.find_any(&[word], max)
+    }
+
+    /// Words within `max` edits of any of `words`, each at its smallest distance, closest and
+    /// most frequent first.
+    pub fn find_any(&self, words: &[&str], max: usize) -> Vec<(&str, usize)> {
+        let mut found: Vec<(&Node, usize)> = vec![];
+        if self.nodes.is_empty() {
+            return vec![];
+        }
+
+        for word in words {
+            let mut stack = vec![0usize];
+            while let Some(cur) = stack.pop() {
+                let node = &self.nodes[cur];
+                let dist = levenshtein(&node.word, word);
+                if dist <= max {
+                    found.push((node, dist));
+                }
+                for (d, idx) in &node.children {
+                    let d = *d as usize;
+                    if d + max >= dist && d <= dist + max {
+                        stack.push(*idx as usize);
+                    }
+                }
+            }
+        }
+
+        found.sort_by(|(a, da), (b, db)| (da, a.rank, &a.word).cmp(&(db, b.rank, &b.word)));
+        // the first of a word is its closest
+        let mut seen = std::collections::HashSet::new();
+        found
+            .into_iter()
+            .filter(|(node, _)| seen.insert(node.word.as_str()))
+            .map(|(node, dist)| (node.word.as_str(), dist))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&(self.nodes.len() as u32).to_le_bytes())?;
+        for node in &self.nodes {
+            let word = node.word.as_bytes();
+            w.write_all(&(word.len() as u16).to_le_bytes())?;
+            w.write_all(word)?;
+            w.write_all(&node.rank.to_le_bytes())?;
+            w.write_all(&(node.children.len() as u16).to_le_bytes())?;
+            for (dist, idx) in &node.children {
+                w.write_all(&[*dist])?;
+                w.write_all(&idx.to_le_bytes())?;
+            }
+        }
+        w.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a BK-tree"));
+        }
+
+        let len = read_u32(&mut r)? as usize;
+        let mut nodes = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut word = vec![0u8; read_u16(&mut r)? as usize];
+            r.read_exact(&mut word)?;
+            let word = String::from_utf8(word)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let rank = read_u32(&mut r)?;
+            let count = read_u16(&mut r)? as usize;
+            let mut children = Vec::with_capacity(count);
+            for _ in 0..count {
+                let mut dist = [0u8; 1];
+                r.read_exact(&mut dist)?;
+                children.push((dist[0], read_u32(&mut r)?));
+            }
+            nodes.push(Node {
+                word,
+                rank,
+                children,
+            });
+        }
+        Ok(BkTree { nodes })
+    }
+}
+
+fn read_u16<R: Read>(r: &mut R) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Levenshtein distance on the original casing, so `May` and `may` are both kept in the tree.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Frequency rank from `frq` (COCA) or `bnc`, unranked words last.
+pub fn rank(frq: Option<&str>, bnc: Option<&str>) -> u32 {
+    [frq, bnc]
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.trim().parse::<u32>().ok())
+        .find(|r| *r > 0)
+        .unwrap_or(u32::MAX)
+}
+
+/// The BK-tree index file of the database, `None` for in-memory databases.
+pub fn index_path() -> Option<PathBuf> {
+    database_path().map(|p| p.with_extension("bktree"))
+}
+
+/// Headwords similar to `word`, best match first. None for in-memory databases, which have
+/// no index.
+pub fn suggest(word: &str, limit: usize) -> Result<Vec<String>, Error> {
+    let Some(tree) = load()? else {
+        return Ok(vec![]);
+    };
+    // one edit for short words, two otherwise
+    let max = if word.chars().count() <= 4 { 1 } else { 2 };
+    // `Helo` should still find `hello`
+    let lower = word.to_lowercase();
+    Ok(tree
+        .find_any(&[word, &lower], max)
+        .into_iter()
+        .take(limit)
+        .map(|(w, _)| w.to_string())
+        .collect())
+}
+
+/// Build the tree and save it to [index_path], `None` for in-memory databases.
+pub async fn rebuild(db: &DatabaseConnection) -> Result<Option<usize>, Error> {
+    let Some(path) = index_path() else {
+        return Ok(None);
+    };
+    let tree = build(db).await?;
+    tree.save(&path)?;
+    Ok(Some(tree.len()))
+}
+
+/// The saved tree, `None` for in-memory databases. One older than the database is still
+/// used, the words it misses are only left out of suggestions.
+fn load() -> Result<Option<BkTree>, Error> {
+    let Some(path) = index_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Err(Error::Database(format!(
+            "Spelling index {} is missing, build it with `{IMPORT_COMMAND}`",
+            path.display()
+        )));
+    }
+    if !is_fresh(&path) {
+        debug!("BK-tree {} is older than the database", path.display());
+    }
+    let tree = BkTree::load(&path).map_err(|e| {
+        Error::Database(format!(
+            "Load spelling index {} failed: {e}, rebuild it with `{IMPORT_COMMAND}`",
+            path.display()
+        ))
+    })?;
+    debug!("Loaded BK-tree from {}", path.display());
+    Ok(Some(tree))
+}
+
+/// Build the tree from single-word headwords.
+pub async fn build(db: &DatabaseConnection) -> Result<BkTree, Error> {
+    let mut words = entities::words::Entity::find()
+        .select_only()
+        .column(entities::words::Column::Word)
+        .column(entities::words::Column::Frq)
+        .column(entities::words::Column::Bnc)
+        .into_tuple::<(String, Option<String>, Option<String>)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|(w, _, _)| !w.is_empty() && w.chars().all(|c| c.is_alphabetic() || c == '-'))
+        .map(|(w, frq, bnc)| (rank(frq.as_deref(), bnc.as_deref()), w))
+        .collect::<Vec<(u32, String)>>();
+    // common words near the root keep the tree shallow where it's searched most
+    words.sort();
+
+    let mut tree = BkTree::default();
+    for (rank, word) in &words {
+        tree.insert(word, *rank);
+    }
+    debug!("Built BK-tree with {} words", tree.len());
+    Ok(tree)
+}
+
+/// Index exists and isn't older than the database.
+pub(crate) fn is_fresh(index: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (
+        modified(index),
+        database_path().and_then(|db| modified(&db)),
+    ) {
+        (Some(index), Some(db)) => index >= db,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{levenshtein, rank, BkTree};
+
+    #[test]
+    fn test_bktree() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("Word", "word"), 1);
+        assert_eq!(rank(Some("0"), Some("120")), 120);
+        assert_eq!(rank(None, Some("")), u32::MAX);
+
+        let mut tree = BkTree::default();
+        for (word, rank) in [
+            ("hello", 10),
+            ("help", 5),
+            ("hell", 50),
+            ("yellow", 20),
+            ("world", 1),
+            ("World", 30),
+        ] {
+            tree.insert(word, rank);
+        }
+        let found: Vec<&str> = tree.find("helo", 1).into_iter().map(|(w, _)| w).collect();
+        assert_eq!(found, vec!["help", "hello", "hell"]);
+        // matches of the lower-cased query rank among the others by frequency
+        let found: Vec<&str> = tree
+            .find_any(&["Wrld", "wrld"], 1)
+            .into_iter()
+            .map(|(w, _)| w)
+            .collect();
+        assert_eq!(found, vec!["world", "World"]);
+
+        let path =
+            std::env::temp_dir().join(format!("runslate_test_{}.bktree", std::process::id()));
+        tree.save(&path).unwrap();
+        let loaded = BkTree::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 6);
+        assert_eq!(loaded.find("wrld", 1), vec![("world", 1)]);
+        assert_eq!(loaded.find("Wrld", 1), vec![("World", 1)]);
+    }
+}
@@ -59,7 +59,7 @@ impl Translator for Google {
 
         // 句子
         if let Some(Value::Array(sentences)) = response.get("sentences") {
-            if let Some(Value::Object(sentence)) = sentences.get(0) {
+            if let Some(Value::Object(sentence)) = sentences.first() {
                 if let Some(Value::String(trans)) = sentence.get("trans") {
                     println!("{}", trans.dye(Colors::BrightBlack))
                 }
@@ -75,7 +75,7 @@ impl Translator for Google {
                     continue;
                 }
                 if let Some(Value::Array(entries)) = dict.get("entry") {
-                    for (idx, entry) in entries.into_iter().enumerate() {
+                    for (idx, entry) in entries.iter().enumerate() {
                         if let Value::Object(entry) = entry {
                             if let Some(Value::String(word)) = entry.get("word") {
                                 print!("  {}", word.dye(Colors::BrightBlack));
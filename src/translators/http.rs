@@ -0,0 +1,353 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use async_trait::async_trait;
+use log::{debug, trace};
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use serde_json_path::JsonPath;
+
+use crate::{
+    errors::Error,
+    utils::{
+        dyer::{Colors, Dye},
+        env_loader,
+    },
+};
+
+use super::{Lang, Translator};
+
+const CONFIG_FILE: &str = ".config/runslate/translators.toml";
+
+/// User-defined HTTP backends, declared in `RUNSLATE_HTTP_CONFIG`
+/// (default: `~/.config/runslate/translators.toml`).
+///
+/// ```toml
+/// [[backends]]
+/// name = "corp"
+/// method = "POST"
+/// url = "https://mt.corp.example/api/translate?to={target}"
+/// body = '{"text": "{words}", "from": "{source}"}'
+/// headers = { Authorization = "Bearer token", Content-Type = "application/json" }
+/// langs = { zh = "zh-CN", auto = "" }
+///
+/// [backends.extract]
+/// translation = "$.data.translations[*].text"
+/// phonetic = "$.data.phonetic"
+/// senses = "$.data.senses[*]"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Http {
+    /// Backend name, the first declared backend if `None`.
+    pub backend: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub backends: Vec<Backend>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Backend {
+    pub name: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Url template, values are percent-encoded.
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Form fields templates, sent url-encoded.
+    #[serde(default)]
+    pub form: BTreeMap<String, String>,
+    /// Raw body template, values are JSON-escaped.
+    pub body: Option<String>,
+    /// Maps lower-case [Lang] names to the codes the backend expects.
+    #[serde(default)]
+    pub langs: BTreeMap<String, String>,
+    #[serde(default)]
+    pub extract: Extract,
+}
+
+/// JSONPath expressions applied on the response.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Extract {
+    pub translation: Option<String>,
+    pub phonetic: Option<String>,
+    pub senses: Option<String>,
+}
+
+fn default_method() -> String {
+    String::from("GET")
+}
+
+#[async_trait]
+impl Translator for Http {
+    async fn translate(&self, words: &str, source: &Lang, target: &Lang) -> Result<Value, Error> {
+        trace!("Http: Start to send request.");
+
+        let backend = find_backend(&load_config()?, self.backend.as_deref())?;
+        let source = backend.lang(source);
+        let target = backend.lang(target);
+        let url = render(&backend.url, words, &source, &target, percent_encode);
+
+        let method = Method::from_bytes(backend.method.to_uppercase().as_bytes())
+            .map_err(|e| Error::ConfigError(format!("{}: {e}", backend.method)))?;
+        let client = Client::new();
+        let mut request = client.request(method, &url);
+        for (key, value) in &backend.headers {
+            request = request.header(key, value);
+        }
+        if !backend.form.is_empty() {
+            let form: BTreeMap<&str, String> = backend
+                .form
+                .iter()
+                .map(|(k, v)| {
+                    (
+                        k.as_str(),
+                        render(v, words, &source, &target, str::to_string),
+                    )
+                })
+                .collect();
+            request = request.form(&form);
+        }
+        if let Some(body) = &backend.body {
+            request = request.body(render(body, words, &source, &target, json_escape));
+        }
+
+        trace!("Request data generated.");
+        debug!("backend: {}", backend.name);
+        debug!("url: {}", url);
+        debug!("from: {}, to: {}", source, target);
+
+        // error bodies aren't translations, keep them out of the cache
+        let response = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        debug!("{:#?}", response);
+
+        extract(&backend, &response)
+    }
+
+    fn show(&self, response: &Value, more: bool) {
+        trace!("Http: parsing response data.");
+
+        let translation = strings(response.get("translation"));
+        if !translation.is_empty() {
+            println!("{}", translation.join("\n").dye(Colors::BrightWhite));
+        }
+
+        let phonetic = strings(response.get("phonetic"));
+        if !phonetic.is_empty() {
+            println!("{}", phonetic.join("    ").dye(Colors::BrightYellow));
+        }
+
+        if more {
+            if let Some(Value::Array(senses)) = response.get("senses") {
+                for sense in senses {
+                    match sense {
+                        Value::String(s) => println!("{}", s.dye(Colors::Cyan)),
+                        v => println!("{}", v.to_string().dye(Colors::Cyan)),
+                    }
+                }
+            }
+        }
+
+        trace!("Response parsed.");
+    }
+}
+
+impl Backend {
+    fn lang(&self, lang: &Lang) -> String {
+        let name = lang.to_string().to_lowercase();
+        self.langs.get(&name).cloned().unwrap_or(name)
+    }
+}
+
+/// Load backends from `RUNSLATE_HTTP_CONFIG` or `~/.config/runslate/translators.toml`.
+pub fn load_config() -> Result<HttpConfig, Error> {
+    let path = match env_loader::load("RUNSLATE_HTTP_CONFIG") {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => home::home_dir()
+            .ok_or(Error::FileNotExist("home dir".to_string()))?
+            .join(CONFIG_FILE),
+    };
+    debug!("Load http backends from {}", path.display());
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| Error::ConfigError(format!("{}: {e}", path.display())))?;
+    toml::from_str::<HttpConfig>(&content)
+        .map_err(|e| Error::ConfigError(format!("{}: {e}", path.display())))
+}
+
+fn find_backend(config: &HttpConfig, name: Option<&str>) -> Result<Backend, Error> {
+    match name {
+        Some(name) => config.backends.iter().find(|b| b.name == name),
+        None => config.backends.first(),
+    }
+    .cloned()
+    .ok_or_else(|| {
+        Error::ConfigError(format!(
+            "Http backend not found: {}",
+            name.unwrap_or("<first>")
+        ))
+    })
+}
+
+/// Name of the backend `name` picks, the first one declared if it's `None`, an error if it
+/// isn't declared.
+pub fn backend_name(name: Option<&str>) -> Result<String, Error> {
+    Ok(find_backend(&load_config()?, name)?.name)
+}
+
+/// Replace `{words}`, `{source}` and `{target}` in `template`, in one pass so placeholders in
+/// the substituted values are left alone.
+fn render(
+    template: &str,
+    words: &str,
+    source: &str,
+    target: &str,
+    escape: fn(&str) -> String,
+) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = [
+            ("{words}", words),
+            ("{source}", source),
+            ("{target}", target),
+        ]
+        .into_iter()
+        .find(|(placeholder, _)| rest.starts_with(placeholder));
+        match value {
+            Some((placeholder, value)) => {
+                rendered.push_str(&escape(value));
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Pick translation, phonetic and senses out of `response`.
+fn extract(backend: &Backend, response: &Value) -> Result<Value, Error> {
+    let query = |expr: &Option<String>| -> Result<Vec<Value>, Error> {
+        let Some(expr) = expr else {
+            return Ok(vec![]);
+        };
+        let path = JsonPath::parse(expr)
+            .map_err(|e| Error::ConfigError(format!("{}: {expr}: {e}", backend.name)))?;
+        Ok(path.query(response).all().into_iter().cloned().collect())
+    };
+
+    Ok(json!({
+        "backend": backend.name,
+        "translation": query(&backend.extract.translation)?,
+        "phonetic": query(&backend.extract.phonetic)?,
+        "senses": query(&backend.extract.senses)?,
+    }))
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+fn json_escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                v => v.to_string(),
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{extract, find_backend, json_escape, percent_encode, render, HttpConfig};
+    use crate::translators::Lang;
+
+    const CONFIG: &str = r#"
+[[backends]]
+name = "corp"
+method = "POST"
+url = "https://mt.example/api?q={words}&to={target}"
+body = '{"text": "{words}", "from": "{source}"}'
+langs = { zh = "zh-CN" }
+
+[backends.extract]
+translation = "$.data.translations[*].text"
+senses = "$.data.senses[*]"
+"#;
+
+    #[test]
+    fn test_render_and_extract() {
+        let config: HttpConfig = toml::from_str(CONFIG).unwrap();
+        let backend = find_backend(&config, Some("corp")).unwrap();
+        assert_eq!(backend.lang(&Lang::Zh), "zh-CN");
+        assert_eq!(backend.lang(&Lang::En), "en");
+        assert!(find_backend(&config, Some("missing")).is_err());
+
+        assert_eq!(
+            render(&backend.url, "a/b c", "en", "zh-CN", percent_encode),
+            "https://mt.example/api?q=a%2Fb%20c&to=zh-CN"
+        );
+        assert_eq!(
+            render(
+                backend.body.as_ref().unwrap(),
+                "say \"hi\"",
+                "en",
+                "zh",
+                json_escape
+            ),
+            r#"{"text": "say \"hi\"", "from": "en"}"#
+        );
+        // placeholders typed in the query stay as they are
+        assert_eq!(
+            render(
+                backend.body.as_ref().unwrap(),
+                "{source} {target}",
+                "en",
+                "zh",
+                json_escape
+            ),
+            r#"{"text": "{source} {target}", "from": "en"}"#
+        );
+
+        let response =
+            json!({"data": {"translations": [{"text": "你好"}], "senses": ["greeting"]}});
+        let value = extract(&backend, &response).unwrap();
+        assert_eq!(value["translation"], json!(["你好"]));
+        assert_eq!(value["phonetic"], json!([]));
+        assert_eq!(value["senses"], json!(["greeting"]));
+    }
+}
@@ -3,19 +3,27 @@ use std::fmt::Display;
 use async_trait::async_trait;
 use clap::{Args, ValueEnum};
 use log::{debug, error, info, warn};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
-    cache,
+    cache::{self, CacheKey, Cached},
     errors::Error,
-    translators::{dictionaryapi::DictionaryApi, ecdict::Ecdict, google::Google, youdao::Youdao},
+    translators::{
+        cedict::Cedict, dict::Dict, dictionaryapi::DictionaryApi, ecdict::Ecdict, google::Google,
+        http::Http, wiktionary::Wiktionary, wordnet::Wordnet, youdao::Youdao,
+    },
+    utils::dyer::{Colors, Dye},
 };
 
+pub mod cedict;
+pub mod dict;
 mod dictionaryapi;
 pub mod ecdict;
 mod google;
+pub mod http;
+pub mod wiktionary;
+pub mod wordnet;
 mod youdao;
 
 #[async_trait]
@@ -37,6 +45,21 @@ pub enum Translators {
 
     #[clap(alias = "e")]
     Ecdict,
+
+    Dict,
+
+    #[clap(alias = "w")]
+    Wiktionary,
+
+    #[clap(alias = "wn")]
+    Wordnet,
+
+    #[clap(alias = "c")]
+    Cedict,
+
+    /// User-defined backend, see `--backend`
+    #[clap(alias = "h")]
+    Http,
 }
 
 #[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
@@ -66,6 +89,11 @@ impl Display for Translators {
             Translators::Youdao => write!(f, "youdao"),
             Translators::DictionaryApi => write!(f, "dictionaryapi"),
             Translators::Ecdict => write!(f, "ecdict"),
+            Translators::Dict => write!(f, "dict"),
+            Translators::Wiktionary => write!(f, "wiktionary"),
+            Translators::Wordnet => write!(f, "wordnet"),
+            Translators::Cedict => write!(f, "cedict"),
+            Translators::Http => write!(f, "http"),
         }
     }
 }
@@ -93,9 +121,9 @@ impl Display for Lang {
     }
 }
 
+/// Translator and languages of a query, shared by the query and the cache entry commands.
 #[derive(Debug, Args)]
-#[command(args_conflicts_with_subcommands = true)]
-pub struct QueryArgs {
+pub struct TranslatorArgs {
     /// [enum] Translator
     #[arg(
         short = 't',
@@ -118,10 +146,72 @@ pub struct QueryArgs {
     #[arg(short = 'd', long, default_value = "zh", env = "RUNSLATE_TARGET_LANG")]
     pub target_lang: Lang,
 
+    /// [string] Backend declared in `RUNSLATE_HTTP_CONFIG`, used by `-t http`
+    #[arg(short = 'b', long, env = "RUNSLATE_HTTP_BACKEND")]
+    pub backend: Option<String>,
+}
+
+impl TranslatorArgs {
+    /// Name results of the translator are cached under.
+    pub fn cache_name(&self) -> Result<String, Error> {
+        // user-defined backends share one translator, tell them apart in cache, by the name of
+        // the first one declared without `-b` so reordering the config doesn't mix them up
+        match self.translator {
+            Translators::Http => Ok(format!(
+                "{}.{}",
+                self.translator,
+                http::backend_name(self.backend.as_deref())?
+            )),
+            _ => Ok(self.translator.to_string()),
+        }
+    }
+
+    /// The translator and whether its results are cached, which they are for those going
+    /// online. `reverse` and `relations` are options of ECDICT and WordNet.
+    pub fn build(&self, reverse: bool, relations: bool) -> (Box<dyn Translator>, bool) {
+        match self.translator {
+            Translators::DictionaryApi => (Box::new(DictionaryApi {}), true),
+            Translators::Google => (Box::new(Google {}), true),
+            Translators::Youdao => (Box::new(Youdao {}), true),
+            Translators::Dict => (Box::new(Dict {}), true),
+            Translators::Http => (
+                Box::new(Http {
+                    backend: self.backend.clone(),
+                }),
+                true,
+            ),
+            Translators::Ecdict => (Box::new(Ecdict { reverse }), false),
+            Translators::Wiktionary => (Box::new(Wiktionary {}), false),
+            Translators::Wordnet => (Box::new(Wordnet { relations }), false),
+            Translators::Cedict => (Box::new(Cedict {}), false),
+        }
+    }
+
+    /// Translators which go online, their results are cached. Offline dictionaries aren't.
+    pub fn online(&self) -> Option<Box<dyn Translator>> {
+        let (translator, cached) = self.build(false, false);
+        cached.then_some(translator)
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub translator: TranslatorArgs,
+
     /// [bool] Print more translation info
     #[arg(short, long, default_value = "true", env = "RUNSLATE_SHOW_MORE")]
     pub more: bool,
 
+    /// [bool] Find ECDICT headwords by Chinese meaning or English definition
+    #[arg(short = 'R', long, default_value = "false")]
+    pub reverse: bool,
+
+    /// [bool] Show all WordNet relations: hypernyms, hyponyms, meronyms...
+    #[arg(short = 'r', long, default_value = "false")]
+    pub relations: bool,
+
     /// [bool] Decides if to use cache
     #[arg(short = 'n', long, default_value = "false", env = "RUNSLATE_NO_CACHE")]
     pub no_cache: bool,
@@ -135,86 +225,64 @@ pub struct QueryArgs {
     pub words: Vec<String>,
 }
 
-pub async fn translate(mut args: QueryArgs) {
+pub async fn translate(args: QueryArgs) {
     let words = args.words.join(" ");
-    let translator: &dyn Translator = match args.translator {
-        Translators::DictionaryApi => &DictionaryApi {},
-        Translators::Ecdict => {
-            args.no_cache = true;
-            &Ecdict {}
-        },
-        Translators::Google => &Google {},
-        Translators::Youdao => &Youdao {},
-    };
-    if !args.no_cache {
-        if let Ok(response) = load(
-            &words,
-            &args.source_lang,
-            &args.target_lang,
-            &args.translator,
-        ) {
-            info!("Load querying result from cache successfully.");
-            translator.show(&response, args.more);
+    let (translator, cached) = args.translator.build(args.reverse, args.relations);
+    let no_cache = args.no_cache || !cached;
+    let source_lang = &args.translator.source_lang;
+    let target_lang = &args.translator.target_lang;
+    let translator_name = match args.translator.cache_name() {
+        Ok(name) => name,
+        Err(err) => {
+            error!("{:#?}", err);
             return;
         }
-        warn!("Try load cache failed.")
+    };
+    // shown if the translator fails, replaced if it doesn't
+    let mut stale = None;
+    if !no_cache {
+        match load(&words, source_lang, target_lang, &translator_name).await {
+            Ok(Cached {
+                value,
+                stale: false,
+            }) => {
+                info!("Load querying result from cache successfully.");
+                translator.show(&value, args.more);
+                return;
+            }
+            Ok(Cached { value, stale: true }) => stale = Some(value),
+            Err(_) => warn!("Try load cache failed."),
+        }
     }
 
-    match translator
-        .translate(&words, &args.source_lang, &args.target_lang)
-        .await
-    {
-        Ok(response) => {
+    match (
+        translator.translate(&words, source_lang, target_lang).await,
+        stale,
+    ) {
+        (Ok(response), _) => {
             debug!("{:#?}", &response);
             translator.show(&response, args.more);
-            if !args.no_cache {
-                save(
-                    &words,
-                    &args.target_lang,
-                    &args.source_lang,
-                    &args.translator,
-                    response,
-                );
+            if !no_cache {
+                save(&words, source_lang, target_lang, &translator_name, response).await;
             }
         }
-        Err(err) => error!("{:#?}", err),
+        (Err(err), Some(stale)) => {
+            warn!("{:?}, show the stale cache instead.", err);
+            translator.show(&stale, args.more);
+            println!("{}", "(stale)".dye(Colors::BrightBlack));
+        }
+        // missing or empty databases tell how to import, show it without `--verbose`
+        (Err(Error::Database(msg)), None) => eprintln!("{}", msg.dye(Colors::Red)),
+        (Err(err), None) => error!("{:#?}", err),
     }
 }
 
-fn save(query: &str, sl: &Lang, tl: &Lang, translator: &Translators, value: Value) {
-    let file_name = file_name(query, sl, tl, translator);
-    cache::set(&file_name, value);
-}
-
-fn load(query: &str, sl: &Lang, tl: &Lang, translator: &Translators) -> Result<Value, Error> {
-    let file_name = file_name(query, sl, tl, translator);
-    cache::get::<Value>(file_name)
+async fn save(query: &str, sl: &Lang, tl: &Lang, translator: &str, value: Value) {
+    let key = CacheKey::new(query, sl, tl, translator);
+    cache::set(&key, value).await;
 }
 
-fn file_name(query: &str, sl: &Lang, tl: &Lang, translator: &Translators) -> String {
-    let invalid_path_chars = Regex::new("[/\\\\?%*:|\"<>,;= ]").unwrap();
-    let multi_stub = Regex::new("-{2,}").unwrap();
-
-    let sentence = query.trim();
-    let sentence = invalid_path_chars.replace_all(sentence, "-");
-    let sentence = multi_stub.replace_all(&sentence, "-");
-    format!("{sl}-{translator}-{tl}_{sentence}")
-}
-
-mod test {
-    #[cfg(test)]
-    use crate::translators::{file_name, Lang, Translators};
-
-    #[test]
-    fn test_file_name() {
-        println!(
-            "{}",
-            file_name(
-                " query hello /\\?%*:|\"<>,;= world",
-                &Lang::Auto,
-                &Lang::Ar,
-                &Translators::Google
-            )
-        )
-    }
+async fn load(query: &str, sl: &Lang, tl: &Lang, translator: &str) -> Result<Cached<Value>, Error> {
+    let key = CacheKey::new(query, sl, tl, translator);
+    cache::get::<Value>(&key).await
 }
@@ -0,0 +1,276 @@
+use log::{debug, trace};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde_json::{json, Value};
+
+use crate::{
+    errors::Error,
+    translators::{
+        ecdict::{check_imported, connect_db_for},
+        Lang, Translator,
+    },
+    utils::dyer::{Colors, Dye},
+};
+
+pub mod entities;
+
+/// Name and import command of the dictionary, for the hints of errors.
+const NAME: &str = "Wiktionary";
+const IMPORT_COMMAND: &str = "cargo run --bin wiktionary -- <file.jsonl>";
+
+/// Offline Wiktionary, imported from kaikki.org (wiktextract) JSONL dumps by `bin/wiktionary.rs`.
+pub struct Wiktionary;
+
+#[async_trait::async_trait]
+impl Translator for Wiktionary {
+    async fn translate(&self, words: &str, source: &Lang, target: &Lang) -> Result<Value, Error> {
+        trace!("Wiktionary: Start to query database.");
+
+        let db = connect_db_for(NAME, IMPORT_COMMAND).await?;
+        check_imported::<entities::wiktionary::Entity>(&db, NAME, IMPORT_COMMAND).await?;
+        lookup(&db, words.trim(), source, target).await
+    }
+
+    fn show(&self, response: &Value, more: bool) {
+        trace!("Wiktionary: parsing response data.");
+
+        let Value::Array(entries) = response else {
+            return;
+        };
+        for entry in entries {
+            // word (language) pos
+            let word = entry.get("word").and_then(Value::as_str).unwrap_or("");
+            let lang = entry.get("lang").and_then(Value::as_str).unwrap_or("");
+            let pos = entry.get("pos").and_then(Value::as_str).unwrap_or("");
+            println!(
+                "{} {} {}",
+                word.dye(Colors::BrightWhite),
+                format!("({lang})").dye(Colors::BrightBlack),
+                pos.dye(Colors::Blue)
+            );
+
+            // pronunciations
+            if let Some(Value::Array(sounds)) = entry.get("sounds") {
+                let ipas: Vec<String> = sounds
+                    .iter()
+                    .filter_map(|s| {
+                        let ipa = s.get("ipa")?.as_str()?;
+                        match join_tags(s) {
+                            tags if tags.is_empty() => Some(ipa.to_string()),
+                            tags => Some(format!("{tags} {ipa}")),
+                        }
+                    })
+                    .collect();
+                if !ipas.is_empty() {
+                    println!("{}", ipas.join("    ").dye(Colors::BrightYellow));
+                }
+            }
+
+            // inflections
+            if more {
+                if let Some(Value::Array(forms)) = entry.get("forms") {
+                    let forms: Vec<String> = forms
+                        .iter()
+                        .filter_map(|f| {
+                            let form = f.get("form")?.as_str()?;
+                            match join_tags(f) {
+                                tags if tags.is_empty() => Some(form.to_string()),
+                                tags => Some(format!("{tags}: {form}")),
+                            }
+                        })
+                        .collect();
+                    if !forms.is_empty() {
+                        println!("{}", forms.join("; ").dye(Colors::BrightCyan));
+                    }
+                }
+            }
+
+            // senses
+            if let Some(Value::Array(senses)) = entry.get("senses") {
+                for (idx, sense) in senses.iter().enumerate() {
+                    if let Some(Value::Array(glosses)) = sense.get("glosses") {
+                        let glosses: Vec<&str> = glosses.iter().filter_map(Value::as_str).collect();
+                        let tags = join_tags(sense);
+                        let tags = if tags.is_empty() {
+                            tags
+                        } else {
+                            format!("({tags}) ")
+                        };
+                        println!(
+                            "{}. {}{}",
+                            idx + 1,
+                            tags.dye(Colors::BrightBlack),
+                            glosses.join("; ").dye(Colors::Cyan)
+                        );
+                    }
+                    if more {
+                        if let Some(Value::Array(examples)) = sense.get("examples") {
+                            for example in examples.iter().filter_map(Value::as_str) {
+                                println!("   {}", example.dye(Colors::BrightBlack));
+                            }
+                        }
+                    }
+                }
+            }
+
+            // translations into the target language
+            if let Some(Value::Array(translations)) = entry.get("translations") {
+                let words: Vec<String> = translations
+                    .iter()
+                    .filter_map(|t| {
+                        let word = t.get("word")?.as_str()?;
+                        match t.get("sense").and_then(Value::as_str) {
+                            Some(sense) if more => Some(format!("{word} ({sense})")),
+                            _ => Some(word.to_string()),
+                        }
+                    })
+                    .collect();
+                if !words.is_empty() {
+                    println!("{}", words.join(", ").dye(Colors::Green));
+                }
+            }
+
+            // etymology
+            if more {
+                if let Some(Value::String(etymology)) = entry.get("etymology") {
+                    println!("{}", etymology.dye(Colors::BrightBlack));
+                }
+            }
+
+            println!();
+        }
+
+        trace!("Response parsed.");
+    }
+}
+
+/// Entries of `word` in `source`, with translations into `target` only.
+pub async fn lookup(
+    db: &DatabaseConnection,
+    word: &str,
+    source: &Lang,
+    target: &Lang,
+) -> Result<Value, Error> {
+    let mut select =
+        entities::wiktionary::Entity::find().filter(entities::wiktionary::Column::Word.eq(word));
+    if let Some(codes) = match_lang(source) {
+        select = select.filter(entities::wiktionary::Column::LangCode.is_in(codes.to_vec()));
+    }
+    let rows = select.all(db).await?;
+    debug!("{} entries found.", rows.len());
+
+    let target = match_lang(target);
+    let entries = rows
+        .into_iter()
+        .map(|row| {
+            let translations = parse(&row.translations)
+                .into_iter()
+                .filter(|t| match target {
+                    Some(codes) => t
+                        .get("code")
+                        .and_then(Value::as_str)
+                        .is_some_and(|code| codes.contains(&code)),
+                    None => true,
+                })
+                .collect::<Vec<Value>>();
+            json!({
+                "word": row.word,
+                "lang": row.lang,
+                "lang_code": row.lang_code,
+                "pos": row.pos,
+                "etymology": row.etymology,
+                "senses": parse(&row.senses),
+                "forms": parse(&row.forms),
+                "sounds": parse(&row.sounds),
+                "translations": translations,
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    Ok(Value::Array(entries))
+}
+
+fn parse(column: &Option<String>) -> Vec<Value> {
+    column
+        .as_deref()
+        .and_then(|s| serde_json::from_str::<Vec<Value>>(s).ok())
+        .unwrap_or_default()
+}
+
+fn join_tags(value: &Value) -> String {
+    match value.get("tags") {
+        Some(Value::Array(tags)) => tags
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<&str>>()
+            .join(", "),
+        _ => String::new(),
+    }
+}
+
+/// Wiktionary language codes, `None` means any language.
+fn match_lang(lang: &Lang) -> Option<&'static [&'static str]> {
+    match lang {
+        // Chinese translations are mostly tagged as Mandarin
+        Lang::Zh | Lang::Zht => Some(&["zh", "cmn"]),
+        Lang::Yue => Some(&["yue"]),
+        Lang::Auto => None,
+        Lang::En => Some(&["en"]),
+        Lang::Fr => Some(&["fr"]),
+        Lang::De => Some(&["de"]),
+        Lang::It => Some(&["it"]),
+        Lang::Es => Some(&["es"]),
+        Lang::Pt => Some(&["pt"]),
+        Lang::Ru => Some(&["ru"]),
+        Lang::El => Some(&["el"]),
+        Lang::Ar => Some(&["ar"]),
+        Lang::La => Some(&["la"]),
+        Lang::Ko => Some(&["ko"]),
+        Lang::Ja => Some(&["ja"]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+    use serde_json::Value;
+
+    use super::{entities::wiktionary::ActiveModel, lookup};
+    use crate::translators::Lang;
+
+    #[tokio::test]
+    async fn test_lookup() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (lang_code, lang) in [("en", "English"), ("fr", "French")] {
+            ActiveModel {
+                word: Set("chat".to_string()),
+                lang: Set(lang.to_string()),
+                lang_code: Set(lang_code.to_string()),
+                pos: Set(Some("noun".to_string())),
+                translations: Set(Some(
+                    r#"[{"lang": "Chinese Mandarin", "code": "cmn", "word": "聊天"}, {"lang": "German", "code": "de", "word": "Plaudern"}]"#
+                        .to_string(),
+                )),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let entries = lookup(&db, "chat", &Lang::En, &Lang::Zh).await.unwrap();
+        let Value::Array(entries) = entries else {
+            panic!("not an array")
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["lang"], "English");
+        assert_eq!(entries[0]["translations"].as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["translations"][0]["word"], "聊天");
+        assert_eq!(entries[0]["senses"], Value::Array(vec![]));
+
+        let any = lookup(&db, "chat", &Lang::Auto, &Lang::Auto).await.unwrap();
+        assert_eq!(any.as_array().unwrap().len(), 2);
+        assert_eq!(any[0]["translations"].as_array().unwrap().len(), 2);
+    }
+}
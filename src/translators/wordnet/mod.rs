@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+
+use log::{debug, trace};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use serde_json::{json, Value};
+
+use crate::{
+    errors::Error,
+    translators::{
+        ecdict::{check_imported, connect_db_for},
+        Lang, Translator,
+    },
+    utils::dyer::{Colors, Dye},
+};
+
+use self::entities::{wordnet_pointers, wordnet_senses, wordnet_synsets};
+
+pub mod entities;
+pub mod wndb;
+
+/// Name and import command of the dictionary, for the hints of errors.
+const NAME: &str = "WordNet";
+const IMPORT_COMMAND: &str = "cargo run --bin wordnet -- <dict dir>";
+
+/// Offline English thesaurus, imported from Princeton WordNet by `bin/wordnet.rs`.
+pub struct Wordnet {
+    /// Show all semantic relations instead of synonyms and antonyms only.
+    pub relations: bool,
+}
+
+#[async_trait::async_trait]
+impl Translator for Wordnet {
+    async fn translate(&self, words: &str, _source: &Lang, _target: &Lang) -> Result<Value, Error> {
+        trace!("Wordnet: Start to query database.");
+
+        let db = connect_db_for(NAME, IMPORT_COMMAND).await?;
+        check_imported::<wordnet_synsets::Entity>(&db, NAME, IMPORT_COMMAND).await?;
+        let synsets = query(&db, &wndb::lemma_key(words)).await?;
+        debug!("{} synsets found.", synsets.len());
+
+        Ok(json!({
+            "word": words.trim(),
+            "synsets": synsets,
+        }))
+    }
+
+    fn show(&self, response: &Value, _more: bool) {
+        trace!("Wordnet: parsing response data.");
+
+        let Some(Value::Array(synsets)) = response.get("synsets") else {
+            return;
+        };
+        for (idx, synset) in synsets.iter().enumerate() {
+            let pos = synset.get("pos").and_then(Value::as_str).unwrap_or("");
+            let lemmas = strings(synset.get("lemmas"));
+            println!(
+                "{}. {} {}",
+                idx + 1,
+                format!("({pos})").dye(Colors::Blue),
+                lemmas.join(", ").dye(Colors::BrightWhite)
+            );
+            if let Some(Value::String(gloss)) = synset.get("gloss") {
+                println!("   {}", gloss.dye(Colors::Cyan));
+            }
+
+            if let Some(Value::Array(relations)) = synset.get("relations") {
+                for relation in relations {
+                    let name = relation
+                        .get("relation")
+                        .and_then(Value::as_str)
+                        .unwrap_or("");
+                    if !self.relations && name != "antonym" {
+                        continue;
+                    }
+                    let words = strings(relation.get("words"));
+                    println!(
+                        "   {} {}",
+                        format!("{name}:").dye(Colors::BrightBlack),
+                        words.join(", ").dye(Colors::Green)
+                    );
+                }
+            }
+        }
+
+        trace!("Response parsed.");
+    }
+}
+
+/// Synsets containing `lemma`, with their lemmas, gloss and relations.
+pub async fn query(db: &DatabaseConnection, lemma: &str) -> Result<Vec<Value>, DbErr> {
+    let senses = wordnet_senses::Entity::find()
+        .filter(wordnet_senses::Column::Lemma.eq(lemma))
+        .order_by_asc(wordnet_senses::Column::Id)
+        .all(db)
+        .await?;
+    let synset_ids = senses
+        .iter()
+        .map(|s| s.synset.clone())
+        .collect::<Vec<String>>();
+
+    let mut synset_rows = wordnet_synsets::Entity::find()
+        .filter(wordnet_synsets::Column::Synset.is_in(synset_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.synset.clone(), s))
+        .collect::<HashMap<_, _>>();
+    let pointers = wordnet_pointers::Entity::find()
+        .filter(wordnet_pointers::Column::Synset.is_in(synset_ids.clone()))
+        .order_by_asc(wordnet_pointers::Column::Id)
+        .all(db)
+        .await?;
+    let words = synset_words(
+        db,
+        synset_ids
+            .into_iter()
+            .chain(pointers.iter().map(|p| p.target_synset.clone()))
+            .collect(),
+    )
+    .await?;
+
+    let mut synsets = vec![];
+    for sense in senses {
+        let Some(synset) = synset_rows.remove(&sense.synset) else {
+            continue;
+        };
+        let lemmas = words.get(&sense.synset).cloned().unwrap_or_default();
+
+        // lexical pointers only apply to the word they start from
+        let mut relations: Vec<(String, Vec<String>)> = vec![];
+        for pointer in pointers
+            .iter()
+            .filter(|p| p.synset == sense.synset)
+            .filter(|p| p.source == 0 || p.source == sense.word_num)
+        {
+            let targets = words
+                .get(&pointer.target_synset)
+                .cloned()
+                .unwrap_or_default();
+            let targets = match pointer.target {
+                0 => targets,
+                n => targets.into_iter().skip(n as usize - 1).take(1).collect(),
+            };
+            match relations.iter_mut().find(|(r, _)| r == &pointer.relation) {
+                Some((_, words)) => words.extend(targets),
+                None => relations.push((pointer.relation.clone(), targets)),
+            }
+        }
+
+        synsets.push(json!({
+            "synset": synset.synset,
+            "pos": wndb::pos_name(&synset.pos),
+            "gloss": synset.gloss,
+            "lemmas": lemmas,
+            "relations": relations
+                .into_iter()
+                .map(|(relation, words)| json!({"relation": relation, "words": dedup(words)}))
+                .collect::<Vec<Value>>(),
+        }));
+    }
+    Ok(synsets)
+}
+
+/// Words of each synset, ordered by word number.
+async fn synset_words(
+    db: &DatabaseConnection,
+    synsets: HashSet<String>,
+) -> Result<HashMap<String, Vec<String>>, DbErr> {
+    let mut words: HashMap<String, Vec<String>> = HashMap::new();
+    for sense in wordnet_senses::Entity::find()
+        .filter(wordnet_senses::Column::Synset.is_in(synsets))
+        .order_by_asc(wordnet_senses::Column::WordNum)
+        .all(db)
+        .await?
+    {
+        words.entry(sense.synset).or_default().push(sense.word);
+    }
+    Ok(words)
+}
+
+fn dedup(words: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    words
+        .into_iter()
+        .filter(|w| seen.insert(w.clone()))
+        .collect()
+}
+
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    match value {
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ActiveValue::Set, Database};
+
+    use super::{
+        entities::{wordnet_pointers, wordnet_senses, wordnet_synsets},
+        query,
+    };
+
+    #[tokio::test]
+    async fn test_query() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for (synset, gloss) in [
+            ("01-a", "having a high temperature"),
+            ("02-a", "low in temperature"),
+            ("03-a", "showing affection"),
+        ] {
+            wordnet_synsets::ActiveModel {
+                synset: Set(synset.to_string()),
+                pos: Set("a".to_string()),
+                gloss: Set(Some(gloss.to_string())),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        for (word, synset, word_num) in [
+            ("hot", "01-a", 1),
+            ("heated", "01-a", 2),
+            ("cold", "02-a", 1),
+            ("chilly", "02-a", 2),
+            ("warm", "03-a", 1),
+        ] {
+            wordnet_senses::ActiveModel {
+                lemma: Set(word.to_string()),
+                word: Set(word.to_string()),
+                synset: Set(synset.to_string()),
+                word_num: Set(word_num),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        // `hot` to `cold` only, `heated` to nothing, the whole synset to `warm`
+        for (source, relation, target_synset, target) in
+            [(1, "antonym", "02-a", 1), (0, "similar to", "03-a", 0)]
+        {
+            wordnet_pointers::ActiveModel {
+                synset: Set("01-a".to_string()),
+                source: Set(source),
+                relation: Set(relation.to_string()),
+                target_synset: Set(target_synset.to_string()),
+                target: Set(target),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let hot = query(&db, "hot").await.unwrap();
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0]["pos"], "adjective");
+        assert_eq!(hot[0]["lemmas"], serde_json::json!(["hot", "heated"]));
+        assert_eq!(
+            hot[0]["relations"],
+            serde_json::json!([
+                {"relation": "antonym", "words": ["cold"]},
+                {"relation": "similar to", "words": ["warm"]},
+            ])
+        );
+
+        let heated = query(&db, "heated").await.unwrap();
+        assert_eq!(
+            heated[0]["relations"],
+            serde_json::json!([{"relation": "similar to", "words": ["warm"]}])
+        );
+        assert!(query(&db, "tepid").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Parser of Princeton WordNet database files (`data.noun`, `data.verb`, `data.adj`, `data.adv`),
+//! see wndb(5WN).
+
+/// A synset parsed from one line of a `data.*` file.
+#[derive(Debug, PartialEq)]
+pub struct DataLine {
+    /// `{offset}-{pos}`, satellite adjectives use `a` as they share `data.adj`.
+    pub synset: String,
+    pub pos: String,
+    /// Words in order, their 1-based position is the word number used by pointers.
+    pub words: Vec<String>,
+    pub pointers: Vec<Pointer>,
+    pub gloss: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Pointer {
+    pub relation: String,
+    pub target_synset: String,
+    /// Source word number, 0 for a relation between synsets.
+    pub source: i32,
+    /// Target word number, 0 for a relation between synsets.
+    pub target: i32,
+}
+
+/// Data files to import with the part of speech they hold.
+pub const DATA_FILES: &[(&str, &str)] = &[
+    ("data.noun", "n"),
+    ("data.verb", "v"),
+    ("data.adj", "a"),
+    ("data.adv", "r"),
+];
+
+/// Parse a line of `data.*`, `None` for license header lines and malformed ones.
+pub fn parse_data_line(line: &str) -> Option<DataLine> {
+    // license header lines start with spaces
+    if line.starts_with(' ') {
+        return None;
+    }
+    let (fields, gloss) = line.split_once(" | ").unwrap_or((line, ""));
+    let mut fields = fields.split_whitespace();
+
+    let offset = fields.next()?;
+    let _lex_filenum = fields.next()?;
+    let ss_type = fields.next()?;
+    let pos = file_pos(ss_type);
+
+    let w_cnt = usize::from_str_radix(fields.next()?, 16).ok()?;
+    let mut words = Vec::with_capacity(w_cnt);
+    for _ in 0..w_cnt {
+        words.push(clean_word(fields.next()?));
+        let _lex_id = fields.next()?;
+    }
+
+    let p_cnt = fields.next()?.parse::<usize>().ok()?;
+    let mut pointers = Vec::with_capacity(p_cnt);
+    for _ in 0..p_cnt {
+        let symbol = fields.next()?;
+        let target_offset = fields.next()?;
+        let target_pos = file_pos(fields.next()?);
+        let source_target = fields.next()?;
+        if source_target.len() != 4 {
+            return None;
+        }
+        pointers.push(Pointer {
+            relation: relation_name(symbol, pos).to_string(),
+            target_synset: format!("{target_offset}-{target_pos}"),
+            source: i32::from_str_radix(&source_target[..2], 16).ok()?,
+            target: i32::from_str_radix(&source_target[2..], 16).ok()?,
+        });
+    }
+
+    Some(DataLine {
+        synset: format!("{offset}-{pos}"),
+        pos: pos.to_string(),
+        words,
+        pointers,
+        gloss: gloss.trim().to_string(),
+    })
+}
+
+/// Lemma key used for lookups: lower case, words separated by space.
+pub fn lemma_key(word: &str) -> String {
+    word.trim().to_lowercase().replace(['_', '-'], " ")
+}
+
+/// Readable part of speech.
+pub fn pos_name(pos: &str) -> &'static str {
+    match pos {
+        "n" => "noun",
+        "v" => "verb",
+        "a" => "adjective",
+        "r" => "adverb",
+        _ => "",
+    }
+}
+
+fn file_pos(ss_type: &str) -> &'static str {
+    match ss_type {
+        "n" => "n",
+        "v" => "v",
+        "a" | "s" => "a",
+        "r" => "r",
+        _ => "",
+    }
+}
+
+/// `gallant_(p)` -> `gallant`, `ice_cream` -> `ice cream`
+fn clean_word(word: &str) -> String {
+    let word = match word.find('(') {
+        Some(idx) if word.ends_with(')') => &word[..idx],
+        _ => word,
+    };
+    word.replace('_', " ")
+}
+
+fn relation_name(symbol: &str, pos: &str) -> &'static str {
+    match (symbol, pos) {
+        ("!", _) => "antonym",
+        ("@", _) => "hypernym",
+        ("@i", _) => "instance hypernym",
+        ("~", _) => "hyponym",
+        ("~i", _) => "instance hyponym",
+        ("#m", _) => "member holonym",
+        ("#s", _) => "substance holonym",
+        ("#p", _) => "part holonym",
+        ("%m", _) => "member meronym",
+        ("%s", _) => "substance meronym",
+        ("%p", _) => "part meronym",
+        ("=", _) => "attribute",
+        ("+", _) => "derivationally related",
+        (";c", _) => "domain of topic",
+        ("-c", _) => "member of topic",
+        (";r", _) => "domain of region",
+        ("-r", _) => "member of region",
+        (";u", _) => "domain of usage",
+        ("-u", _) => "member of usage",
+        ("*", _) => "entailment",
+        (">", _) => "cause",
+        ("^", _) => "also see",
+        ("$", _) => "verb group",
+        ("&", _) => "similar to",
+        ("<", _) => "participle of verb",
+        ("\\", "r") => "derived from adjective",
+        ("\\", _) => "pertainym",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lemma_key, parse_data_line, Pointer};
+
+    #[test]
+    fn test_parse_data_line() {
+        let line = "02084071 05 n 03 dog 0 domestic_dog 0 Canis_familiaris 0 002 @ 02083346 n 0000 ! 01234567 n 0101 | a member of the genus Canis; \"the dog barked all night\"  ";
+        let data = parse_data_line(line).unwrap();
+        assert_eq!(data.synset, "02084071-n");
+        assert_eq!(data.words, vec!["dog", "domestic dog", "Canis familiaris"]);
+        assert_eq!(
+            data.pointers[0],
+            Pointer {
+                relation: "hypernym".to_string(),
+                target_synset: "02083346-n".to_string(),
+                source: 0,
+                target: 0,
+            }
+        );
+        assert_eq!(data.pointers[1].relation, "antonym");
+        assert_eq!((data.pointers[1].source, data.pointers[1].target), (1, 1));
+        assert_eq!(
+            data.gloss,
+            "a member of the genus Canis; \"the dog barked all night\""
+        );
+
+        let satellite = "00003939 00 s 01 gallant(p) 0 000 | unusually large";
+        let data = parse_data_line(satellite).unwrap();
+        assert_eq!(data.synset, "00003939-a");
+        assert_eq!(data.words, vec!["gallant"]);
+
+        assert!(parse_data_line("  1 This software and database is being provided").is_none());
+        assert_eq!(lemma_key("Canis_familiaris"), "canis familiaris");
+    }
+}
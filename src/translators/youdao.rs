@@ -86,26 +86,22 @@ impl Translator for Youdao {
 
         // 【可选打印项】单词校验后的结果，主要校验字母大小写、单词前含符号、中文简繁体
         if more {
-            if let Some(v) = response.get("returnPhrase") {
-                if let Value::Array(phrases) = v {
-                    let mut original_phrase = String::new();
-                    for phrase in phrases {
-                        original_phrase.push_str(&phrase.to_string());
-                    }
-                    println!("{}", original_phrase.dye(Colors::BrightBlack));
+            if let Some(Value::Array(phrases)) = response.get("returnPhrase") {
+                let mut original_phrase = String::new();
+                for phrase in phrases {
+                    original_phrase.push_str(&phrase.to_string());
                 }
+                println!("{}", original_phrase.dye(Colors::BrightBlack));
             }
         }
 
         // 翻译结果；查询正确时一定存在
-        if let Some(v) = response.get("translation") {
-            if let Value::Array(phrases) = v {
-                let mut original_phrase = String::new();
-                for phrase in phrases {
-                    original_phrase.push_str(&phrase.to_string());
-                }
-                println!("{}", original_phrase.dye(Colors::BrightWhite));
+        if let Some(Value::Array(phrases)) = response.get("translation") {
+            let mut original_phrase = String::new();
+            for phrase in phrases {
+                original_phrase.push_str(&phrase.to_string());
             }
+            println!("{}", original_phrase.dye(Colors::BrightWhite));
         }
 
         // 词义；基本词典，查词时才有
@@ -118,7 +114,7 @@ impl Translator for Youdao {
             if let Some(Value::String(phonetic_us)) = v.get("us-phonetic") {
                 phonetics.push_str(&format!("美 [{}]    ", phonetic_us));
             }
-            if phonetics.len() > 0 {
+            if !phonetics.is_empty() {
                 println!("{}", phonetics.trim().dye(Colors::BrightYellow));
             }
 
@@ -174,14 +170,14 @@ impl Translator for Youdao {
                         Value::Object(kv) => {
                             if let Some(Value::String(key)) = kv.get("key") {
                                 original_phrase.push_str(key);
-                                original_phrase.push_str(" ");
+                                original_phrase.push(' ');
                             }
                             if let Some(Value::Array(values)) = kv.get("value") {
-                                for (idx, value) in values.into_iter().enumerate() {
+                                for (idx, value) in values.iter().enumerate() {
                                     if let Value::String(v) = value {
                                         original_phrase.push_str(v);
                                         if idx < values.len() - 1 {
-                                            original_phrase.push_str("；")
+                                            original_phrase.push('；')
                                         }
                                     }
                                 }
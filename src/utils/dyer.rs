@@ -7,7 +7,7 @@ pub trait Dye {
 impl Dye for String {
     fn dye(&self, color: Colors) -> String {
         let c: String = color.into();
-        c + &self + COLOR_END
+        c + self + COLOR_END
     }
 }
 
@@ -15,7 +15,7 @@ pub fn load_or_panic(key: &str) -> String {
     let result = env::var(key);
     match result {
         Ok(value) => {
-            if value.len() > 0 {
+            if !value.is_empty() {
                 debug!("[load_or_panic] got value: {}.", key);
                 return value;
             }
@@ -27,7 +27,7 @@ pub fn load_or_panic(key: &str) -> String {
 
 pub fn load_or_default(key: &str, default: &str) -> String {
     if let Ok(value) = env::var(key) {
-        return if value.len() == 0 {
+        return if value.is_empty() {
             debug!("Empty env value, use default: {}.", key);
             String::from(default)
         } else {
@@ -39,7 +39,7 @@ pub fn load_or_default(key: &str, default: &str) -> String {
 }
 
 pub fn load_env_file(file_name: &str) -> Result<String, Error> {
-    let paths = vec![
+    let paths = [
         env::current_dir().unwrap().join(file_name),
         home::home_dir().unwrap().join(file_name),
         home::home_dir()
@@ -57,7 +57,7 @@ pub fn load_env_file(file_name: &str) -> Result<String, Error> {
         .find(|p| p.exists())
         .ok_or(Error::EnvNotExist(".env".to_string()))?;
 
-    if let None = dotenvy::from_path(&file_path).ok() {
+    if dotenvy::from_path(file_path).ok().is_none() {
         return Err(Error::OuterCrateInternalError(String::from(
             "[dotenv] Load .env file failed.",
         )));
@@ -69,7 +69,7 @@ pub fn load_env_file(file_name: &str) -> Result<String, Error> {
 pub fn clear_empty_env(envs: Vec<&str>) {
     for key in envs {
         if let Ok(value) = load(key) {
-            if value.trim().len() == 0 {
+            if value.trim().is_empty() {
                 unsafe {
                     env::remove_var(key);
                 }